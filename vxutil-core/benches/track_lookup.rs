@@ -0,0 +1,52 @@
+//! Clip lookup on long tracks
+//!
+//! Lookup time should stay nearly flat as the clip count grows.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::time::Duration;
+use vxutil_core::media::MediaId;
+use vxutil_core::timeline::{Clip, Track, TrackId, TrackType};
+use vxutil_core::types::{TimeRange, Timecode};
+
+/// Track of back-to-back two second clips
+fn track(clip_count: usize) -> Track {
+    let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+    for i in 0..clip_count {
+        track.add_clip(Clip::new(
+            format!("clip {i}"),
+            MediaId::new(),
+            Timecode::from_seconds(i as f64 * 2.0),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(2.0),
+        ));
+    }
+    track
+}
+
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("track_lookup");
+    for clip_count in [100, 1_000, 10_000] {
+        let track = track(clip_count);
+        let middle = Timecode::from_seconds(clip_count as f64);
+        // Warm the index outside the measurement
+        track.clip_at_time(middle);
+
+        group.bench_with_input(
+            BenchmarkId::new("clip_at_time", clip_count),
+            &middle,
+            |b, &time| b.iter(|| track.clip_at_time(black_box(time)).is_some()),
+        );
+
+        let range = TimeRange::new(middle, Duration::from_secs(10));
+        group.bench_with_input(
+            BenchmarkId::new("clips_in_range", clip_count),
+            &range,
+            |b, &range| b.iter(|| track.clips_in_range(black_box(range)).len()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lookup);
+criterion_main!(benches);
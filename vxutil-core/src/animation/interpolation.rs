@@ -0,0 +1,192 @@
+//! Interpolation modes between keyframes
+
+use serde::{Deserialize, Serialize};
+
+/// How a value travels from one keyframe to the next
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Keep the value until the next keyframe is reached
+    Hold,
+    /// Straight line between the two keyframes
+    #[default]
+    Linear,
+    /// Timing curve between the two keyframes
+    Bezier(CubicBezier),
+}
+
+impl Interpolation {
+    /// Map normalized progress (0.0 to 1.0) between two keyframes to an interpolation factor
+    pub fn factor(&self, progress: f64) -> f64 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            Interpolation::Hold => 0.0,
+            Interpolation::Linear => progress,
+            Interpolation::Bezier(curve) => curve.solve(progress),
+        }
+    }
+}
+
+impl From<Easing> for Interpolation {
+    fn from(easing: Easing) -> Self {
+        match easing {
+            Easing::Linear => Interpolation::Linear,
+            other => Interpolation::Bezier(other.curve()),
+        }
+    }
+}
+
+/// Cubic Bezier timing curve from (0, 0) to (1, 1), CSS `cubic-bezier()` style
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CubicBezier {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+}
+
+impl CubicBezier {
+    /// Create a new curve. X coordinates are clamped to 0.0 to 1.0 so the curve stays a function of time.
+    pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+        Self {
+            x1: x1.clamp(0.0, 1.0),
+            y1,
+            x2: x2.clamp(0.0, 1.0),
+            y2,
+        }
+    }
+
+    fn sample(p1: f64, p2: f64, t: f64) -> f64 {
+        // B(t) = 3(1-t)^2 t p1 + 3(1-t) t^2 p2 + t^3
+        let u = 1.0 - t;
+        3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t
+    }
+
+    fn sample_derivative(p1: f64, p2: f64, t: f64) -> f64 {
+        let u = 1.0 - t;
+        3.0 * u * u * p1 + 6.0 * u * t * (p2 - p1) + 3.0 * t * t * (1.0 - p2)
+    }
+
+    /// Evaluate the curve's Y for a given X (progress)
+    pub fn solve(&self, x: f64) -> f64 {
+        Self::sample(self.y1, self.y2, self.parameter_at(x))
+    }
+
+    /// The part of the curve after progress `x`, rescaled to run from (0, 0) to
+    /// (1, 1) again, so easing continues exactly where the cut was made. None when
+    /// the remaining part cannot be rescaled because its Y span is zero.
+    pub fn split_at(&self, x: f64) -> Option<CubicBezier> {
+        let t = self.parameter_at(x);
+        // De Casteljau: control points of the curve from t to 1
+        let right = |p1: f64, p2: f64| {
+            let lerp = |a: f64, b: f64| a + (b - a) * t;
+            let (p12, p23) = (lerp(p1, p2), lerp(p2, 1.0));
+            (Self::sample(p1, p2, t), lerp(p12, p23), p23)
+        };
+        let (x0, x1, x2) = right(self.x1, self.x2);
+        let (y0, y1, y2) = right(self.y1, self.y2);
+
+        let (x_span, y_span) = (1.0 - x0, 1.0 - y0);
+        if x_span.abs() < 1e-9 || y_span.abs() < 1e-9 {
+            return None;
+        }
+        Some(CubicBezier::new(
+            (x1 - x0) / x_span,
+            (y1 - y0) / y_span,
+            (x2 - x0) / x_span,
+            (y2 - y0) / y_span,
+        ))
+    }
+
+    /// Curve parameter whose X is `x`
+    fn parameter_at(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+
+        // Newton-Raphson first, it converges quickly for well-behaved curves
+        let mut t = x;
+        for _ in 0..8 {
+            let error = Self::sample(self.x1, self.x2, t) - x;
+            if error.abs() < 1e-7 {
+                return t;
+            }
+            let slope = Self::sample_derivative(self.x1, self.x2, t);
+            if slope.abs() < 1e-7 {
+                break;
+            }
+            t -= error / slope;
+        }
+
+        // Fall back to bisection
+        let (mut low, mut high) = (0.0, 1.0);
+        t = x;
+        for _ in 0..64 {
+            let value = Self::sample(self.x1, self.x2, t);
+            if (value - x).abs() < 1e-7 {
+                break;
+            }
+            if value < x {
+                low = t;
+            } else {
+                high = t;
+            }
+            t = (low + high) / 2.0;
+        }
+        t
+    }
+}
+
+/// Easing presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    EaseInCubic,
+    EaseOutCubic,
+    EaseInOutCubic,
+    EaseOutBack,
+}
+
+impl Easing {
+    /// Timing curve for this preset
+    pub fn curve(&self) -> CubicBezier {
+        match self {
+            Easing::Linear => CubicBezier::new(0.0, 0.0, 1.0, 1.0),
+            Easing::EaseIn => CubicBezier::new(0.42, 0.0, 1.0, 1.0),
+            Easing::EaseOut => CubicBezier::new(0.0, 0.0, 0.58, 1.0),
+            Easing::EaseInOut => CubicBezier::new(0.42, 0.0, 0.58, 1.0),
+            Easing::EaseInCubic => CubicBezier::new(0.32, 0.0, 0.67, 0.0),
+            Easing::EaseOutCubic => CubicBezier::new(0.33, 1.0, 0.68, 1.0),
+            Easing::EaseInOutCubic => CubicBezier::new(0.65, 0.0, 0.35, 1.0),
+            Easing::EaseOutBack => CubicBezier::new(0.34, 1.56, 0.64, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bezier_endpoints() {
+        for easing in [Easing::EaseIn, Easing::EaseOut, Easing::EaseInOutCubic] {
+            let curve = easing.curve();
+            assert!(curve.solve(0.0).abs() < 1e-6);
+            assert!((curve.solve(1.0) - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_ease_in_out_is_symmetric() {
+        let curve = Easing::EaseInOut.curve();
+        assert!((curve.solve(0.5) - 0.5).abs() < 1e-6);
+        assert!(curve.solve(0.25) < 0.25);
+        assert!(curve.solve(0.75) > 0.75);
+    }
+
+    #[test]
+    fn test_linear_bezier_matches_linear() {
+        let curve = Easing::Linear.curve();
+        assert!((curve.solve(0.3) - 0.3).abs() < 1e-6);
+    }
+}
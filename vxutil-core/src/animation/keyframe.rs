@@ -0,0 +1,206 @@
+//! Keyframes and keyframe curves
+
+use serde::{Deserialize, Serialize};
+
+use super::Interpolation;
+use crate::types::Timecode;
+
+/// A value pinned at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Time relative to the start of the clip
+    pub time: Timecode,
+    pub value: f32,
+
+    /// Interpolation used from this keyframe to the next one
+    pub interpolation: Interpolation,
+}
+
+impl Keyframe {
+    pub fn new(time: Timecode, value: f32, interpolation: Interpolation) -> Self {
+        Self {
+            time,
+            value,
+            interpolation,
+        }
+    }
+}
+
+/// A curve defined by keyframes sorted by time
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct KeyframeCurve {
+    keyframes: Vec<Keyframe>,
+}
+
+impl KeyframeCurve {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All keyframes, sorted by time
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Insert a keyframe, replacing any existing keyframe at the same time
+    pub fn set_keyframe(&mut self, keyframe: Keyframe) {
+        match self
+            .keyframes
+            .binary_search_by_key(&keyframe.time, |k| k.time)
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    /// Remove the keyframe at exactly the given time
+    pub fn remove_keyframe(&mut self, time: Timecode) -> Option<Keyframe> {
+        self.keyframes
+            .binary_search_by_key(&time, |k| k.time)
+            .ok()
+            .map(|index| self.keyframes.remove(index))
+    }
+
+    /// Evaluate the curve at a clip-relative time.
+    /// Before the first keyframe and after the last one the value is held.
+    pub fn value_at(&self, time: Timecode) -> Option<f32> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        // Index of the first keyframe strictly after `time`
+        let next_index = self.keyframes.partition_point(|k| k.time <= time);
+        let from = &self.keyframes[next_index - 1];
+        let to = &self.keyframes[next_index];
+
+        let span = (to.time.as_duration() - from.time.as_duration()).as_secs_f64();
+        let elapsed = (time.as_duration() - from.time.as_duration()).as_secs_f64();
+        let factor = from.interpolation.factor(elapsed / span);
+
+        Some(from.value + (to.value - from.value) * factor as f32)
+    }
+
+    /// Area under the curve from time zero to `until`, in value-seconds.
+    /// Values are held before the first and after the last keyframe, as in `value_at`.
+    pub fn integral(&self, until: Timecode) -> Option<f64> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        let until = until.as_seconds();
+
+        let mut area = f64::from(first.value) * first.time.as_seconds().min(until);
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let start = from.time.as_seconds();
+            if until <= start {
+                return Some(area);
+            }
+            let end = until.min(to.time.as_seconds());
+            let span = to.time.as_seconds() - start;
+
+            area += match from.interpolation {
+                Interpolation::Hold => f64::from(from.value) * (end - start),
+                interpolation => simpson(start, end, |t| {
+                    let factor = interpolation.factor((t - start) / span);
+                    f64::from(from.value) + f64::from(to.value - from.value) * factor
+                }),
+            };
+        }
+        area += f64::from(last.value) * (until - last.time.as_seconds()).max(0.0);
+
+        Some(area)
+    }
+}
+
+/// Composite Simpson's rule, exact for linear segments
+fn simpson(start: f64, end: f64, f: impl Fn(f64) -> f64) -> f64 {
+    const STEPS: usize = 32;
+    let h = (end - start) / STEPS as f64;
+    let inner: f64 = (1..STEPS)
+        .map(|i| f(start + i as f64 * h) * if i % 2 == 1 { 4.0 } else { 2.0 })
+        .sum();
+    (f(start) + inner + f(end)) * h / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Easing;
+
+    fn curve(interpolation: Interpolation) -> KeyframeCurve {
+        let mut curve = KeyframeCurve::new();
+        curve.set_keyframe(Keyframe::new(
+            Timecode::from_seconds(1.0),
+            0.0,
+            interpolation,
+        ));
+        curve.set_keyframe(Keyframe::new(
+            Timecode::from_seconds(3.0),
+            10.0,
+            Interpolation::Linear,
+        ));
+        curve
+    }
+
+    #[test]
+    fn test_linear_interpolation() {
+        let curve = curve(Interpolation::Linear);
+        assert_eq!(curve.value_at(Timecode::from_seconds(0.0)), Some(0.0));
+        assert_eq!(curve.value_at(Timecode::from_seconds(2.0)), Some(5.0));
+        assert_eq!(curve.value_at(Timecode::from_seconds(5.0)), Some(10.0));
+    }
+
+    #[test]
+    fn test_hold_interpolation() {
+        let curve = curve(Interpolation::Hold);
+        assert_eq!(curve.value_at(Timecode::from_seconds(2.9)), Some(0.0));
+        assert_eq!(curve.value_at(Timecode::from_seconds(3.0)), Some(10.0));
+    }
+
+    #[test]
+    fn test_eased_interpolation() {
+        let curve = curve(Easing::EaseIn.into());
+        let value = curve.value_at(Timecode::from_seconds(2.0)).unwrap();
+        assert!(value > 0.0 && value < 5.0);
+    }
+
+    #[test]
+    fn test_integral() {
+        let integral =
+            |curve: &KeyframeCurve, t: f64| curve.integral(Timecode::from_seconds(t)).unwrap();
+
+        // 0 held until 1s, ramp to 10 by 3s, then 10 held
+        let linear = curve(Interpolation::Linear);
+        assert!((integral(&linear, 1.0) - 0.0).abs() < 1e-9);
+        assert!((integral(&linear, 3.0) - 10.0).abs() < 1e-9);
+        assert!((integral(&linear, 4.0) - 20.0).abs() < 1e-9);
+
+        let hold = curve(Interpolation::Hold);
+        assert!((integral(&hold, 3.0) - 0.0).abs() < 1e-9);
+
+        // Symmetric ease-in-out covers the same area as linear
+        let eased = curve(Easing::EaseInOut.into());
+        assert!((integral(&eased, 3.0) - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_set_keyframe_replaces_same_time() {
+        let mut curve = curve(Interpolation::Linear);
+        curve.set_keyframe(Keyframe::new(
+            Timecode::from_seconds(3.0),
+            20.0,
+            Interpolation::Linear,
+        ));
+        assert_eq!(curve.keyframes().len(), 2);
+        assert_eq!(curve.value_at(Timecode::from_seconds(3.0)), Some(20.0));
+    }
+}
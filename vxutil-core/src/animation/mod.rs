@@ -0,0 +1,12 @@
+//! Keyframe animation
+//!
+//! Any numeric effect parameter can be either a constant or a keyframed curve.
+//! Keyframe times are relative to the start of the clip the effect belongs to.
+
+mod interpolation;
+mod keyframe;
+mod value;
+
+pub use interpolation::{CubicBezier, Easing, Interpolation};
+pub use keyframe::{Keyframe, KeyframeCurve};
+pub use value::AnimatedValue;
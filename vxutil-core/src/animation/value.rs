@@ -0,0 +1,109 @@
+//! Animatable parameter value
+
+use serde::{Deserialize, Serialize};
+
+use super::{Interpolation, Keyframe, KeyframeCurve};
+use crate::types::Timecode;
+
+/// A numeric parameter that is either constant or animated with keyframes.
+///
+/// Constants serialize as a plain number, so effects saved before keyframes
+/// existed still load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AnimatedValue {
+    Constant(f32),
+    Animated(KeyframeCurve),
+}
+
+impl AnimatedValue {
+    pub fn constant(value: f32) -> Self {
+        AnimatedValue::Constant(value)
+    }
+
+    pub fn is_animated(&self) -> bool {
+        matches!(self, AnimatedValue::Animated(_))
+    }
+
+    /// Evaluate the parameter at a clip-relative time
+    pub fn value_at(&self, time: Timecode) -> f32 {
+        match self {
+            AnimatedValue::Constant(value) => *value,
+            AnimatedValue::Animated(curve) => curve.value_at(time).unwrap_or_default(),
+        }
+    }
+
+    /// Add or replace a keyframe, turning a constant into an animated value.
+    /// The former constant is kept as a keyframe at time zero.
+    pub fn set_keyframe(&mut self, time: Timecode, value: f32, interpolation: Interpolation) {
+        if let AnimatedValue::Constant(constant) = *self {
+            let mut curve = KeyframeCurve::new();
+            if time != Timecode::from_seconds(0.0) {
+                curve.set_keyframe(Keyframe::new(
+                    Timecode::from_seconds(0.0),
+                    constant,
+                    interpolation,
+                ));
+            }
+            *self = AnimatedValue::Animated(curve);
+        }
+
+        if let AnimatedValue::Animated(curve) = self {
+            curve.set_keyframe(Keyframe::new(time, value, interpolation));
+        }
+    }
+
+    /// Remove a keyframe. Falls back to a constant once a single keyframe remains.
+    pub fn remove_keyframe(&mut self, time: Timecode) -> Option<Keyframe> {
+        let AnimatedValue::Animated(curve) = self else {
+            return None;
+        };
+
+        let removed = curve.remove_keyframe(time);
+        if let [only] = curve.keyframes() {
+            *self = AnimatedValue::Constant(only.value);
+        }
+        removed
+    }
+
+    /// Drop all keyframes, keeping the given value
+    pub fn set_constant(&mut self, value: f32) {
+        *self = AnimatedValue::Constant(value);
+    }
+}
+
+impl From<f32> for AnimatedValue {
+    fn from(value: f32) -> Self {
+        AnimatedValue::Constant(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_to_animated() {
+        let mut value = AnimatedValue::constant(1.0);
+        value.set_keyframe(Timecode::from_seconds(2.0), 0.0, Interpolation::Linear);
+
+        assert!(value.is_animated());
+        assert_eq!(value.value_at(Timecode::from_seconds(0.0)), 1.0);
+        assert_eq!(value.value_at(Timecode::from_seconds(1.0)), 0.5);
+
+        value.remove_keyframe(Timecode::from_seconds(2.0));
+        assert_eq!(value, AnimatedValue::Constant(1.0));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = serde_json::to_string(&AnimatedValue::constant(0.5)).unwrap();
+        assert_eq!(json, "0.5");
+
+        let mut value = AnimatedValue::constant(0.0);
+        value.set_keyframe(Timecode::from_seconds(1.0), 1.0, Interpolation::Hold);
+        let json = serde_json::to_string(&value).unwrap();
+        let deserialized: AnimatedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(value, deserialized);
+    }
+}
@@ -2,14 +2,18 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::animation::AnimatedValue;
+
 /// Blur effect
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlurEffect {
-    pub radius: f32, // blur radius in pixels
+    pub radius: AnimatedValue, // blur radius in pixels
 }
 
 impl Default for BlurEffect {
     fn default() -> Self {
-        Self { radius: 5.0 }
+        Self {
+            radius: AnimatedValue::constant(5.0),
+        }
     }
 }
@@ -2,22 +2,24 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::animation::AnimatedValue;
+
 /// Color correction effect
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorCorrectEffect {
-    pub brightness: f32, // -1.0 to 1.0
-    pub contrast: f32,   // -1.0 to 1.0
-    pub saturation: f32, // 0.0 to 2.0
-    pub hue: f32,        // -180.0 to 180.0 degrees
+    pub brightness: AnimatedValue, // -1.0 to 1.0
+    pub contrast: AnimatedValue,   // -1.0 to 1.0
+    pub saturation: AnimatedValue, // 0.0 to 2.0
+    pub hue: AnimatedValue,        // -180.0 to 180.0 degrees
 }
 
 impl Default for ColorCorrectEffect {
     fn default() -> Self {
         Self {
-            brightness: 0.0,
-            contrast: 0.0,
-            saturation: 1.0,
-            hue: 0.0,
+            brightness: AnimatedValue::constant(0.0),
+            contrast: AnimatedValue::constant(0.0),
+            saturation: AnimatedValue::constant(1.0),
+            hue: AnimatedValue::constant(0.0),
         }
     }
 }
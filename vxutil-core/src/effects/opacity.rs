@@ -2,14 +2,18 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::animation::AnimatedValue;
+
 /// Opacity effect
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpacityEffect {
-    pub opacity: f32, // 0.0 to 1.0
+    pub opacity: AnimatedValue, // 0.0 to 1.0
 }
 
 impl Default for OpacityEffect {
     fn default() -> Self {
-        Self { opacity: 1.0 }
+        Self {
+            opacity: AnimatedValue::constant(1.0),
+        }
     }
 }
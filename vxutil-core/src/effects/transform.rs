@@ -2,24 +2,26 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::animation::AnimatedValue;
+
 /// Transform effect (position, scale, rotation)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformEffect {
-    pub position_x: f32,
-    pub position_y: f32,
-    pub scale_x: f32,
-    pub scale_y: f32,
-    pub rotation: f32, // degrees
+    pub position_x: AnimatedValue,
+    pub position_y: AnimatedValue,
+    pub scale_x: AnimatedValue,
+    pub scale_y: AnimatedValue,
+    pub rotation: AnimatedValue, // degrees
 }
 
 impl Default for TransformEffect {
     fn default() -> Self {
         Self {
-            position_x: 0.0,
-            position_y: 0.0,
-            scale_x: 1.0,
-            scale_y: 1.0,
-            rotation: 0.0,
+            position_x: AnimatedValue::constant(0.0),
+            position_y: AnimatedValue::constant(0.0),
+            scale_x: AnimatedValue::constant(1.0),
+            scale_y: AnimatedValue::constant(1.0),
+            rotation: AnimatedValue::constant(0.0),
         }
     }
 }
@@ -31,8 +33,8 @@ mod tests {
     #[test]
     fn test_transform_default() {
         let effect = TransformEffect::default();
-        assert_eq!(effect.scale_x, 1.0);
-        assert_eq!(effect.scale_y, 1.0);
-        assert_eq!(effect.rotation, 0.0);
+        assert_eq!(effect.scale_x, AnimatedValue::Constant(1.0));
+        assert_eq!(effect.scale_y, AnimatedValue::Constant(1.0));
+        assert_eq!(effect.rotation, AnimatedValue::Constant(0.0));
     }
 }
@@ -0,0 +1,99 @@
+//! Solid color and gradient mattes
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Color;
+
+/// Solid color matte
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolidColorGenerator {
+    pub color: Color,
+}
+
+impl Default for SolidColorGenerator {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+        }
+    }
+}
+
+/// Shape of a gradient
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GradientKind {
+    /// Linear gradient along the given angle (degrees, 0 = left to right)
+    Linear { angle: f32 },
+    /// Radial gradient from the frame center to the farthest corner
+    Radial,
+}
+
+/// Color at a position along the gradient
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    pub position: f32, // 0.0 to 1.0
+    pub color: Color,
+}
+
+/// Gradient matte
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradientGenerator {
+    pub kind: GradientKind,
+    pub stops: Vec<GradientStop>,
+}
+
+impl GradientGenerator {
+    /// Color at position `t` (0.0 to 1.0) along the gradient
+    pub fn color_at(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let Some(first) = self.stops.first() else {
+            return Color::TRANSPARENT;
+        };
+        if t <= first.position {
+            return first.color;
+        }
+
+        for pair in self.stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if t <= to.position {
+                let span = to.position - from.position;
+                if span <= f32::EPSILON {
+                    return to.color;
+                }
+                return from.color.lerp(to.color, (t - from.position) / span);
+            }
+        }
+
+        self.stops.last().map(|s| s.color).unwrap_or(first.color)
+    }
+}
+
+impl Default for GradientGenerator {
+    fn default() -> Self {
+        Self {
+            kind: GradientKind::Linear { angle: 90.0 },
+            stops: vec![
+                GradientStop {
+                    position: 0.0,
+                    color: Color::BLACK,
+                },
+                GradientStop {
+                    position: 1.0,
+                    color: Color::WHITE,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradient_color_at() {
+        let gradient = GradientGenerator::default();
+        assert_eq!(gradient.color_at(0.0), Color::BLACK);
+        assert_eq!(gradient.color_at(1.0), Color::WHITE);
+        assert_eq!(gradient.color_at(0.5), Color::rgb(0.5, 0.5, 0.5));
+    }
+}
@@ -0,0 +1,43 @@
+//! Generators
+//!
+//! Clip sources that are synthesized instead of decoded from a media file:
+//! text titles and color mattes. The actual rasterization is done in vxutil-engine.
+
+use serde::{Deserialize, Serialize};
+
+mod matte;
+mod text;
+
+pub use matte::{GradientGenerator, GradientKind, GradientStop, SolidColorGenerator};
+pub use text::{TextAlignment, TextGenerator, TextOutline, TextShadow, VerticalAlignment};
+
+/// Generator that can be used as a clip source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Generator {
+    Text(TextGenerator),
+    SolidColor(SolidColorGenerator),
+    Gradient(GradientGenerator),
+}
+
+impl Generator {
+    pub fn name(&self) -> &str {
+        match self {
+            Generator::Text(_) => "Text",
+            Generator::SolidColor(_) => "Solid Color",
+            Generator::Gradient(_) => "Gradient",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generator_serialization() {
+        let generator = Generator::Text(TextGenerator::new("Lower third"));
+        let json = serde_json::to_string(&generator).unwrap();
+        let deserialized: Generator = serde_json::from_str(&json).unwrap();
+        assert_eq!(generator.name(), deserialized.name());
+    }
+}
@@ -0,0 +1,97 @@
+//! Text title generator
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Color;
+
+/// Horizontal alignment of each line of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextAlignment {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Vertical alignment of the text block around its anchor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum VerticalAlignment {
+    Top,
+    #[default]
+    Middle,
+    Bottom,
+}
+
+/// Outline drawn around each glyph
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TextOutline {
+    pub color: Color,
+    pub width: f32, // pixels
+}
+
+/// Drop shadow drawn behind the text
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TextShadow {
+    pub color: Color,
+    pub offset_x: f32, // pixels
+    pub offset_y: f32, // pixels
+    pub blur: f32,     // blur radius in pixels
+}
+
+/// Text title generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextGenerator {
+    /// Text content, lines are separated by '\n'
+    pub text: String,
+    pub font_family: String,
+    pub font_size: f32, // pixels
+    pub color: Color,
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
+    pub alignment: TextAlignment,
+    pub vertical_alignment: VerticalAlignment,
+
+    /// Line height as a multiple of the font size
+    pub line_spacing: f32,
+
+    /// Anchor position, normalized to the frame (0.0 to 1.0)
+    pub anchor_x: f32,
+    pub anchor_y: f32,
+}
+
+impl TextGenerator {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Lines of text to lay out
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text.lines()
+    }
+
+    /// Distance between baselines in pixels
+    pub fn line_height(&self) -> f32 {
+        self.font_size * self.line_spacing
+    }
+}
+
+impl Default for TextGenerator {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font_family: "Sans".to_string(),
+            font_size: 64.0,
+            color: Color::WHITE,
+            outline: None,
+            shadow: None,
+            alignment: TextAlignment::default(),
+            vertical_alignment: VerticalAlignment::default(),
+            line_spacing: 1.2,
+            anchor_x: 0.5,
+            anchor_y: 0.5,
+        }
+    }
+}
@@ -1,11 +1,13 @@
 //! VxUtil Core - Business logic and domain models
 //!
 //! This crate contains the core business logic for VxUtil video editor,
-//! including project management, timeline models, media library, and effects system.
+//! including project management, timeline models, media library, effects and generators.
 //! It has NO UI dependencies and NO media processing implementation.
 
+pub mod animation;
 pub mod effects;
 pub mod error;
+pub mod generators;
 pub mod media;
 pub mod project;
 pub mod timeline;
@@ -0,0 +1,89 @@
+//! Media bins - folders for organising the media library
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+use super::MediaItem;
+
+/// Unique identifier for a bin
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BinId(Uuid);
+
+impl BinId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for BinId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Field media items in a bin are sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BinSortKey {
+    #[default]
+    Name,
+    MediaType,
+    Duration,
+    ImportedAt,
+}
+
+/// Sort order of the items in a bin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BinSort {
+    pub key: BinSortKey,
+    pub descending: bool,
+}
+
+impl BinSort {
+    pub fn new(key: BinSortKey, descending: bool) -> Self {
+        Self { key, descending }
+    }
+
+    /// Compare two items by this sort order, falling back to name
+    pub fn compare(&self, a: &MediaItem, b: &MediaItem) -> Ordering {
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let ordering = match self.key {
+            BinSortKey::Name => by_name(),
+            BinSortKey::MediaType => (a.media_type as u8).cmp(&(b.media_type as u8)),
+            BinSortKey::Duration => a
+                .duration_seconds()
+                .unwrap_or(0.0)
+                .total_cmp(&b.duration_seconds().unwrap_or(0.0)),
+            BinSortKey::ImportedAt => a.imported_at.cmp(&b.imported_at),
+        }
+        .then_with(by_name);
+
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// A folder in the media library. Bins without a parent sit at the library root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bin {
+    pub id: BinId,
+    pub name: String,
+    pub parent: Option<BinId>,
+
+    #[serde(default)]
+    pub sort: BinSort,
+}
+
+impl Bin {
+    pub fn new(name: String, parent: Option<BinId>) -> Self {
+        Self {
+            id: BinId::new(),
+            name,
+            parent,
+            sort: BinSort::default(),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Smart collections - saved media queries
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{MediaItem, MediaType};
+use crate::types::{LabelColor, Resolution};
+
+/// Unique identifier for a smart collection
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CollectionId(Uuid);
+
+impl CollectionId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for CollectionId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Condition on media items. Items with unknown metadata never match a
+/// condition on that metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MediaQuery {
+    MediaType(MediaType),
+    /// Duration of at least this many seconds
+    MinDuration(f64),
+    /// Duration of at most this many seconds
+    MaxDuration(f64),
+    /// At least this wide and this high
+    MinResolution(Resolution),
+    /// Codec name, case-insensitive
+    Codec(String),
+    /// Has this tag, case-insensitive
+    Tag(String),
+    /// Rated at least this many stars
+    MinRating(u8),
+    Label(LabelColor),
+    /// Name, tags or notes contain the text, case-insensitive
+    Text(String),
+    All(Vec<MediaQuery>),
+    Any(Vec<MediaQuery>),
+    Not(Box<MediaQuery>),
+}
+
+impl MediaQuery {
+    pub fn matches(&self, item: &MediaItem) -> bool {
+        let metadata = &item.metadata;
+        match self {
+            MediaQuery::MediaType(media_type) => item.media_type == *media_type,
+            MediaQuery::MinDuration(seconds) => metadata.duration.is_some_and(|d| d >= *seconds),
+            MediaQuery::MaxDuration(seconds) => metadata.duration.is_some_and(|d| d <= *seconds),
+            MediaQuery::MinResolution(min) => metadata
+                .resolution
+                .is_some_and(|r| r.width >= min.width && r.height >= min.height),
+            MediaQuery::Codec(codec) => metadata
+                .codec
+                .as_ref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(codec)),
+            MediaQuery::Tag(tag) => item.has_tag(tag),
+            MediaQuery::MinRating(stars) => item.rating.is_some_and(|r| r >= *stars),
+            MediaQuery::Label(label) => item.label == Some(*label),
+            MediaQuery::Text(text) => item.matches_text(text),
+            MediaQuery::All(queries) => queries.iter().all(|q| q.matches(item)),
+            MediaQuery::Any(queries) => queries.iter().any(|q| q.matches(item)),
+            MediaQuery::Not(query) => !query.matches(item),
+        }
+    }
+}
+
+/// A saved query. Its contents are evaluated on access, so they follow the
+/// library as items are imported and edited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartCollection {
+    pub id: CollectionId,
+    pub name: String,
+    pub query: MediaQuery,
+}
+
+impl SmartCollection {
+    pub fn new(name: String, query: MediaQuery) -> Self {
+        Self {
+            id: CollectionId::new(),
+            name,
+            query,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Duplicate media detection on import
+
+use serde::{Deserialize, Serialize};
+
+use super::MediaId;
+
+/// What importing a file that is already in the library does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DuplicatePolicy {
+    /// Import anyway and report the existing copies so the user can be warned
+    #[default]
+    Warn,
+    /// Reuse the existing item instead of adding a new one
+    Reuse,
+}
+
+/// Outcome of importing one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportResult {
+    /// The new item, or the reused one
+    pub id: MediaId,
+
+    /// False if an existing item was reused
+    pub added: bool,
+
+    /// Other items with the same content
+    pub duplicates: Vec<MediaId>,
+}
@@ -0,0 +1,45 @@
+//! Content fingerprints for recognising media files
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::Result;
+
+/// Bytes hashed at each end of the file
+const SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// Fast content fingerprint: file size plus hashes of the first and last 64 KiB.
+/// Cheap enough to compute on import, even for large camera files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub size: u64,
+    pub head: u64,
+    pub tail: u64,
+}
+
+impl Fingerprint {
+    pub fn compute(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        let mut buffer = Vec::with_capacity(SAMPLE_SIZE as usize);
+        (&mut file).take(SAMPLE_SIZE).read_to_end(&mut buffer)?;
+        let head = fnv1a(&buffer);
+
+        buffer.clear();
+        file.seek(SeekFrom::Start(size.saturating_sub(SAMPLE_SIZE)))?;
+        file.take(SAMPLE_SIZE).read_to_end(&mut buffer)?;
+        let tail = fnv1a(&buffer);
+
+        Ok(Self { size, head, tail })
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,250 @@
+//! Numbered image sequences (`shot_0001.exr`, `shot_0002.exr`, ...)
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::types::{FrameRate, Timecode};
+use crate::{Result, VxError};
+
+/// Frame numbers a sequence may span per file found; larger spans are not
+/// treated as sequences, which also bounds the list of missing frames
+const MAX_SPAN_PER_FILE: u64 = 4;
+
+/// A run of numbered still images played as video. Frame files live next to the
+/// media item's path, which points at the first frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageSequence {
+    /// File name part before the frame number
+    pub prefix: String,
+
+    /// File name part after the frame number, including the extension
+    pub suffix: String,
+
+    /// Minimum number of digits in the frame number (zero padded)
+    pub padding: usize,
+
+    pub first: u64,
+    pub last: u64,
+
+    /// Frame numbers between `first` and `last` with no file
+    pub missing: Vec<u64>,
+
+    /// Playback rate assigned by the user; image files carry no timing
+    pub frame_rate: FrameRate,
+}
+
+impl ImageSequence {
+    /// Find the sequence `path` belongs to by scanning its folder for files with the
+    /// same name pattern. Returns None if the file is not numbered, has no siblings
+    /// or the numbers are too far apart to be frames of one sequence.
+    pub fn detect(path: &Path, frame_rate: FrameRate) -> Result<Option<Self>> {
+        if !frame_rate.is_valid() {
+            return Err(VxError::InvalidParameter(format!(
+                "Invalid frame rate {}/{}",
+                frame_rate.numerator, frame_rate.denominator
+            )));
+        }
+        let Some((prefix, digits, suffix)) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(split_frame_number)
+        else {
+            return Ok(None);
+        };
+        let dir = path.parent().unwrap_or(Path::new("."));
+
+        let mut frames: Vec<(u64, usize)> = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let name = entry.file_name();
+            let Some((p, d, s)) = name.to_str().and_then(split_frame_number) else {
+                continue;
+            };
+            if p == prefix
+                && s.eq_ignore_ascii_case(suffix)
+                && let Ok(number) = d.parse()
+            {
+                frames.push((number, d.len()));
+            }
+        }
+
+        // Unpadded numbers grow in length, so the shortest one gives the padding
+        let padding = frames
+            .iter()
+            .map(|(_, len)| *len)
+            .min()
+            .unwrap_or(digits.len());
+        let mut numbers: Vec<u64> = frames
+            .into_iter()
+            .filter(|(number, len)| *len == padding || number.to_string().len() == *len)
+            .map(|(number, _)| number)
+            .collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        let (Some(&first), Some(&last)) = (numbers.first(), numbers.last()) else {
+            return Ok(None);
+        };
+        if numbers.len() < 2 {
+            return Ok(None);
+        }
+        // Numbers this sparse are more likely timestamps or takes than frames
+        if last - first >= numbers.len() as u64 * MAX_SPAN_PER_FILE {
+            return Ok(None);
+        }
+
+        let mut sequence = Self {
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            padding,
+            first,
+            last,
+            missing: Vec::new(),
+            frame_rate,
+        };
+        sequence.missing = missing_numbers(&numbers, sequence.frame_range());
+        Ok(Some(sequence))
+    }
+
+    /// Display name such as `shot_[0001-0240].exr`
+    pub fn name(&self) -> String {
+        format!(
+            "{}[{:0width$}-{:0width$}]{}",
+            self.prefix,
+            self.first,
+            self.last,
+            self.suffix,
+            width = self.padding
+        )
+    }
+
+    pub fn frame_range(&self) -> RangeInclusive<u64> {
+        self.first..=self.last
+    }
+
+    /// Number of frames from first to last, including missing ones
+    pub fn frame_count(&self) -> u64 {
+        self.last - self.first + 1
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frame_count() as f64 / self.frame_rate.as_f64())
+    }
+
+    /// File name of a frame number
+    pub fn file_name(&self, number: u64) -> String {
+        format!(
+            "{}{:0width$}{}",
+            self.prefix,
+            number,
+            self.suffix,
+            width = self.padding
+        )
+    }
+
+    /// Frame number shown at a time from the sequence start, clamped to the sequence
+    pub fn frame_at(&self, time: Timecode) -> u64 {
+        let offset = (time.as_seconds() * self.frame_rate.as_f64()).floor() as u64;
+        (self.first + offset).min(self.last)
+    }
+
+    /// Frame number whose file is shown at a time. Missing frames hold the closest
+    /// earlier frame that exists.
+    pub fn available_frame_at(&self, time: Timecode) -> u64 {
+        let mut number = self.frame_at(time);
+        while number > self.first && self.missing.binary_search(&number).is_ok() {
+            number -= 1;
+        }
+        number
+    }
+
+    /// Runs of consecutive missing frames
+    pub fn gaps(&self) -> Vec<RangeInclusive<u64>> {
+        let mut gaps: Vec<RangeInclusive<u64>> = Vec::new();
+        for &number in &self.missing {
+            match gaps.last_mut() {
+                Some(gap) if *gap.end() + 1 == number => *gap = *gap.start()..=number,
+                _ => gaps.push(number..=number),
+            }
+        }
+        gaps
+    }
+}
+
+/// Split a file name into prefix, trailing frame number digits and suffix
+/// (extension), e.g. `shot_0001.exr` into `shot_`, `0001` and `.exr`
+fn split_frame_number(name: &str) -> Option<(&str, &str, &str)> {
+    let stem_end = name.rfind('.').unwrap_or(name.len());
+    let (stem, suffix) = name.split_at(stem_end);
+    let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &stem[prefix.len()..];
+    (!digits.is_empty()).then_some((prefix, digits, suffix))
+}
+
+/// Numbers in `range` not in the sorted `numbers`
+fn missing_numbers(numbers: &[u64], range: RangeInclusive<u64>) -> Vec<u64> {
+    range
+        .filter(|number| numbers.binary_search(number).is_err())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frame_number() {
+        assert_eq!(
+            split_frame_number("shot_0001.exr"),
+            Some(("shot_", "0001", ".exr"))
+        );
+        assert_eq!(split_frame_number("12.dpx"), Some(("", "12", ".dpx")));
+        assert_eq!(split_frame_number("shot.png"), None);
+    }
+
+    #[test]
+    fn test_detect_with_gaps() {
+        let root = std::env::temp_dir().join(format!("vxutil-sequence-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        for number in [8, 9, 10, 11, 14, 15, 17] {
+            fs::write(root.join(format!("shot_{number:03}.png")), b"frame").unwrap();
+        }
+        fs::write(root.join("shot_001.jpg"), b"other extension").unwrap();
+        fs::write(root.join("plate_001.png"), b"other prefix").unwrap();
+
+        let sequence = ImageSequence::detect(&root.join("shot_010.png"), FrameRate::FPS_24)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sequence.name(), "shot_[008-017].png");
+        assert_eq!(sequence.frame_count(), 10);
+        assert_eq!(sequence.gaps(), vec![12..=13, 16..=16]);
+        assert_eq!(sequence.frame_at(Timecode::from_seconds(0.2)), 12);
+        assert_eq!(sequence.available_frame_at(Timecode::from_seconds(0.2)), 11);
+        assert_eq!(sequence.frame_at(Timecode::from_seconds(60.0)), 17);
+
+        assert!(
+            ImageSequence::detect(&root.join("plate_001.png"), FrameRate::FPS_24)
+                .unwrap()
+                .is_none()
+        );
+        assert!(ImageSequence::detect(&root.join("shot_010.png"), FrameRate::new(0, 1)).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_sparse_numbers_are_not_a_sequence() {
+        let root = std::env::temp_dir().join(format!("vxutil-sequence-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("IMG_1.png"), b"photo").unwrap();
+        fs::write(root.join("IMG_1700000000.png"), b"photo").unwrap();
+
+        let sequence = ImageSequence::detect(&root.join("IMG_1.png"), FrameRate::FPS_24).unwrap();
+        assert!(sequence.is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
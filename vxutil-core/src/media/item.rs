@@ -1,9 +1,12 @@
 //! Media item
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use chrono::{DateTime, Utc};
-use super::{MediaId, MediaMetadata, MediaType};
+
+use super::{BinId, Fingerprint, ImageSequence, MediaId, MediaMetadata, MediaType};
+use crate::types::{FrameRate, LabelColor, Timecode};
+use crate::{Result, VxError};
 
 /// A media item represents a source file in the project
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +22,36 @@ pub struct MediaItem {
 
     /// Import timestamp
     pub imported_at: DateTime<Utc>,
+
+    /// Bin containing this item (None for the library root)
+    #[serde(default)]
+    pub bin: Option<BinId>,
+
+    /// User tags, unique ignoring case
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Star rating from 1 to 5
+    #[serde(default)]
+    pub rating: Option<u8>,
+
+    #[serde(default)]
+    pub label: Option<LabelColor>,
+
+    #[serde(default)]
+    pub notes: String,
+
+    /// Content fingerprint taken on import, used to recognise the file when relinking
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+
+    /// The file could not be found; a placeholder is rendered until it is relinked
+    #[serde(default)]
+    pub offline: bool,
+
+    /// Set for numbered image sequences imported as one video item; `path` is the first frame
+    #[serde(default)]
+    pub image_sequence: Option<ImageSequence>,
 }
 
 impl MediaItem {
@@ -37,6 +70,14 @@ impl MediaItem {
             metadata: MediaMetadata::default(),
             thumbnail_path: None,
             imported_at: Utc::now(),
+            bin: None,
+            tags: Vec::new(),
+            rating: None,
+            label: None,
+            notes: String::new(),
+            fingerprint: None,
+            offline: false,
+            image_sequence: None,
         }
     }
 
@@ -45,6 +86,76 @@ impl MediaItem {
         self.path.exists()
     }
 
+    /// Add a tag. Returns false if the item already has it.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || self.has_tag(tag) {
+            return false;
+        }
+        self.tags.push(tag.to_string());
+        true
+    }
+
+    /// Remove a tag, ignoring case. Returns false if the item did not have it.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let count = self.tags.len();
+        self.tags.retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+        self.tags.len() != count
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    /// Set the star rating (1 to 5, None to clear)
+    pub fn set_rating(&mut self, rating: Option<u8>) -> Result<()> {
+        if rating.is_some_and(|r| !(1..=5).contains(&r)) {
+            return Err(VxError::InvalidParameter(
+                "Rating must be between 1 and 5 stars".to_string(),
+            ));
+        }
+        self.rating = rating;
+        Ok(())
+    }
+
+    /// Check whether the name, a tag or the notes contain `text` (case-insensitive)
+    pub fn matches_text(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.name.to_lowercase().contains(&text)
+            || self.tags.iter().any(|t| t.to_lowercase().contains(&text))
+            || self.notes.to_lowercase().contains(&text)
+    }
+
+    /// File to decode for a source time: the frame file of an image sequence
+    /// (holding the previous frame over gaps), otherwise the media file itself
+    pub fn frame_path(&self, source_time: Timecode) -> PathBuf {
+        match &self.image_sequence {
+            Some(sequence) => self
+                .path
+                .with_file_name(sequence.file_name(sequence.available_frame_at(source_time))),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Change the playback rate of an image sequence, updating its duration
+    pub fn set_sequence_frame_rate(&mut self, frame_rate: FrameRate) -> Result<()> {
+        let Some(sequence) = &mut self.image_sequence else {
+            return Err(VxError::InvalidParameter(
+                "Media item is not an image sequence".to_string(),
+            ));
+        };
+        if !frame_rate.is_valid() {
+            return Err(VxError::InvalidParameter(format!(
+                "Invalid frame rate {}/{}",
+                frame_rate.numerator, frame_rate.denominator
+            )));
+        }
+        sequence.frame_rate = frame_rate;
+        self.metadata.frame_rate = Some(frame_rate);
+        self.metadata.duration = Some(sequence.duration().as_secs_f64());
+        Ok(())
+    }
+
     /// Get duration in seconds (if available)
     pub fn duration_seconds(&self) -> Option<f64> {
         self.metadata.duration
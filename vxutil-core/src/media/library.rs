@@ -1,14 +1,34 @@
 //! Media library
 
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use super::{MediaId, MediaItem, MediaType};
+use super::relink::{moved_roots, rate_candidate, scan_directories};
+use super::{
+    Bin, BinId, BinSort, CollectionId, DuplicatePolicy, Fingerprint, ImageSequence, ImportResult,
+    MediaId, MediaItem, MediaQuery, MediaType, RelinkCandidate, RelinkProposal, SmartCollection,
+};
+use crate::types::FrameRate;
+use crate::{Result, VxError};
 
 /// Media library manages all imported media items
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaLibrary {
     items: HashMap<MediaId, MediaItem>,
+
+    /// Bins (folders) organising the items
+    #[serde(default)]
+    bins: HashMap<BinId, Bin>,
+
+    /// Sort order of the library root
+    #[serde(default)]
+    root_sort: BinSort,
+
+    /// Saved smart collections
+    #[serde(default)]
+    collections: Vec<SmartCollection>,
 }
 
 impl Default for MediaLibrary {
@@ -21,6 +41,9 @@ impl MediaLibrary {
     pub fn new() -> Self {
         Self {
             items: HashMap::new(),
+            bins: HashMap::new(),
+            root_sort: BinSort::default(),
+            collections: Vec::new(),
         }
     }
 
@@ -31,6 +54,105 @@ impl MediaLibrary {
         id
     }
 
+    /// Import a file, fingerprinting it to detect content already in the library
+    pub fn import(
+        &mut self,
+        path: PathBuf,
+        media_type: MediaType,
+        policy: DuplicatePolicy,
+    ) -> Result<ImportResult> {
+        let fingerprint = Fingerprint::compute(&path)?;
+        let duplicates: Vec<MediaId> = self
+            .duplicates_of(&fingerprint)
+            .into_iter()
+            .map(|item| item.id.clone())
+            .collect();
+
+        if policy == DuplicatePolicy::Reuse
+            && let Some((first, rest)) = duplicates.split_first()
+        {
+            return Ok(ImportResult {
+                id: first.clone(),
+                added: false,
+                duplicates: rest.to_vec(),
+            });
+        }
+
+        let mut item = MediaItem::new(path, media_type);
+        item.fingerprint = Some(fingerprint);
+        item.metadata.file_size = fingerprint.size;
+        Ok(ImportResult {
+            id: self.add_item(item),
+            added: true,
+            duplicates,
+        })
+    }
+
+    /// Import the numbered image sequence `path` belongs to as one video item
+    /// playing at `frame_rate`. Missing frames are recorded as gaps.
+    pub fn import_image_sequence(&mut self, path: &Path, frame_rate: FrameRate) -> Result<MediaId> {
+        let sequence = ImageSequence::detect(path, frame_rate)?.ok_or_else(|| {
+            VxError::InvalidParameter(format!(
+                "{} is not part of an image sequence",
+                path.display()
+            ))
+        })?;
+
+        let mut item = MediaItem::new(
+            path.with_file_name(sequence.file_name(sequence.first)),
+            MediaType::Video,
+        );
+        item.name = sequence.name();
+        item.metadata.frame_rate = Some(frame_rate);
+        item.metadata.duration = Some(sequence.duration().as_secs_f64());
+        item.image_sequence = Some(sequence);
+        Ok(self.add_item(item))
+    }
+
+    /// Items whose content matches a fingerprint, oldest import first
+    pub fn duplicates_of(&self, fingerprint: &Fingerprint) -> Vec<&MediaItem> {
+        let mut items: Vec<&MediaItem> = self
+            .items
+            .values()
+            .filter(|item| item.fingerprint.as_ref() == Some(fingerprint))
+            .collect();
+        items.sort_by(|a, b| import_order(a, b));
+        items
+    }
+
+    /// Groups of two or more items with the same content, each oldest import first
+    pub fn duplicate_groups(&self) -> Vec<Vec<MediaId>> {
+        let mut groups: HashMap<&Fingerprint, Vec<&MediaItem>> = HashMap::new();
+        for item in self.items.values() {
+            if let Some(fingerprint) = &item.fingerprint {
+                groups.entry(fingerprint).or_default().push(item);
+            }
+        }
+        let mut groups: Vec<Vec<&MediaItem>> =
+            groups.into_values().filter(|g| g.len() > 1).collect();
+        for group in groups.iter_mut() {
+            group.sort_by(|a, b| import_order(a, b));
+        }
+        groups.sort_by(|a, b| import_order(a[0], b[0]));
+        groups
+            .into_iter()
+            .map(|group| group.into_iter().map(|item| item.id.clone()).collect())
+            .collect()
+    }
+
+    /// Fingerprint online items added without one (e.g. from older projects).
+    /// Returns the number of items fingerprinted.
+    pub fn compute_fingerprints(&mut self) -> Result<usize> {
+        let mut count = 0;
+        for item in self.items.values_mut() {
+            if item.fingerprint.is_none() && item.exists() {
+                item.fingerprint = Some(Fingerprint::compute(&item.path)?);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     /// Remove a media item by ID
     pub fn remove_item(&mut self, id: &MediaId) -> Option<MediaItem> {
         self.items.remove(id)
@@ -51,6 +173,11 @@ impl MediaLibrary {
         self.items.values()
     }
 
+    /// Get mutable references to all media items
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut MediaItem> {
+        self.items.values_mut()
+    }
+
     /// Get all video items
     pub fn video_items(&self) -> impl Iterator<Item = &MediaItem> {
         self.items
@@ -72,15 +199,68 @@ impl MediaLibrary {
             .filter(|item| item.media_type == MediaType::Image)
     }
 
-    /// Search media items by name (case-insensitive)
+    /// Search media items by name, tags and notes (case-insensitive)
     pub fn search(&self, query: &str) -> Vec<&MediaItem> {
-        let query_lower = query.to_lowercase();
         self.items
             .values()
-            .filter(|item| item.name.to_lowercase().contains(&query_lower))
+            .filter(|item| item.matches_text(query))
             .collect()
     }
 
+    /// Items matching a query
+    pub fn query(&self, query: &MediaQuery) -> Vec<&MediaItem> {
+        self.items
+            .values()
+            .filter(|item| query.matches(item))
+            .collect()
+    }
+
+    /// Every tag used in the library, sorted and unique ignoring case
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self
+            .items
+            .values()
+            .flat_map(|item| item.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_by_key(|t| t.to_lowercase());
+        tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        tags
+    }
+
+    /// Save a smart collection
+    pub fn add_collection(&mut self, name: String, query: MediaQuery) -> CollectionId {
+        let collection = SmartCollection::new(name, query);
+        let id = collection.id.clone();
+        self.collections.push(collection);
+        id
+    }
+
+    pub fn remove_collection(&mut self, id: &CollectionId) -> Option<SmartCollection> {
+        let index = self.collections.iter().position(|c| &c.id == id)?;
+        Some(self.collections.remove(index))
+    }
+
+    pub fn get_collection_mut(&mut self, id: &CollectionId) -> Option<&mut SmartCollection> {
+        self.collections.iter_mut().find(|c| &c.id == id)
+    }
+
+    /// All smart collections
+    pub fn collections(&self) -> &[SmartCollection] {
+        &self.collections
+    }
+
+    /// Current contents of a smart collection, sorted like the library root
+    pub fn collection_items(&self, id: &CollectionId) -> Result<Vec<&MediaItem>> {
+        let collection = self
+            .collections
+            .iter()
+            .find(|c| &c.id == id)
+            .ok_or_else(|| VxError::NotFound("Smart collection".to_string()))?;
+        let mut items = self.query(&collection.query);
+        items.sort_by(|a, b| self.root_sort.compare(a, b));
+        Ok(items)
+    }
+
     /// Count total items
     pub fn count(&self) -> usize {
         self.items.len()
@@ -91,6 +271,158 @@ impl MediaLibrary {
         self.items.clear();
     }
 
+    /// Create a bin inside `parent` (the library root if None)
+    pub fn create_bin(&mut self, name: String, parent: Option<&BinId>) -> Result<BinId> {
+        self.check_bin(parent)?;
+        let bin = Bin::new(name, parent.cloned());
+        let id = bin.id.clone();
+        self.bins.insert(id.clone(), bin);
+        Ok(id)
+    }
+
+    /// Get a bin by ID
+    pub fn get_bin(&self, id: &BinId) -> Option<&Bin> {
+        self.bins.get(id)
+    }
+
+    pub fn rename_bin(&mut self, id: &BinId, name: String) -> Result<()> {
+        let bin = self
+            .bins
+            .get_mut(id)
+            .ok_or_else(|| VxError::NotFound("Bin".to_string()))?;
+        bin.name = name;
+        Ok(())
+    }
+
+    /// Delete a bin. Its items and sub-bins move up into its parent.
+    pub fn remove_bin(&mut self, id: &BinId) -> Result<Bin> {
+        let bin = self
+            .bins
+            .remove(id)
+            .ok_or_else(|| VxError::NotFound("Bin".to_string()))?;
+        for child in self
+            .bins
+            .values_mut()
+            .filter(|b| b.parent.as_ref() == Some(id))
+        {
+            child.parent = bin.parent.clone();
+        }
+        for item in self
+            .items
+            .values_mut()
+            .filter(|i| i.bin.as_ref() == Some(id))
+        {
+            item.bin = bin.parent.clone();
+        }
+        Ok(bin)
+    }
+
+    /// Move a bin into another bin (the library root if None)
+    pub fn move_bin(&mut self, id: &BinId, parent: Option<&BinId>) -> Result<()> {
+        self.check_bin(Some(id))?;
+        self.check_bin(parent)?;
+        if parent.is_some_and(|p| self.is_within(p, id)) {
+            return Err(VxError::InvalidParameter(
+                "Cannot move a bin into itself".to_string(),
+            ));
+        }
+        if let Some(bin) = self.bins.get_mut(id) {
+            bin.parent = parent.cloned();
+        }
+        Ok(())
+    }
+
+    /// Move a media item into a bin (the library root if None)
+    pub fn move_item(&mut self, id: &MediaId, bin: Option<&BinId>) -> Result<()> {
+        self.check_bin(bin)?;
+        let item = self
+            .items
+            .get_mut(id)
+            .ok_or_else(|| VxError::NotFound("Media item".to_string()))?;
+        item.bin = bin.cloned();
+        Ok(())
+    }
+
+    /// Bins directly inside `parent` (the library root if None), sorted by name
+    pub fn child_bins(&self, parent: Option<&BinId>) -> Vec<&Bin> {
+        let mut bins: Vec<&Bin> = self
+            .bins
+            .values()
+            .filter(|b| b.parent.as_ref() == parent)
+            .collect();
+        bins.sort_by_key(|b| b.name.to_lowercase());
+        bins
+    }
+
+    /// Items directly inside a bin (the library root if None), in the bin's sort order
+    pub fn bin_items(&self, bin: Option<&BinId>) -> Vec<&MediaItem> {
+        let sort = match bin {
+            Some(id) => self.bins.get(id).map(|b| b.sort).unwrap_or_default(),
+            None => self.root_sort,
+        };
+        let mut items: Vec<&MediaItem> = self
+            .items
+            .values()
+            .filter(|i| i.bin.as_ref() == bin)
+            .collect();
+        items.sort_by(|a, b| sort.compare(a, b));
+        items
+    }
+
+    /// Set the sort order of a bin (the library root if None)
+    pub fn set_bin_sort(&mut self, bin: Option<&BinId>, sort: BinSort) -> Result<()> {
+        match bin {
+            Some(id) => {
+                self.bins
+                    .get_mut(id)
+                    .ok_or_else(|| VxError::NotFound("Bin".to_string()))?
+                    .sort = sort;
+            }
+            None => self.root_sort = sort,
+        }
+        Ok(())
+    }
+
+    /// Search items by name, tags and notes inside a bin and every bin below it, in
+    /// the bin's sort order
+    pub fn search_in_bin(&self, query: &str, bin: Option<&BinId>) -> Vec<&MediaItem> {
+        let Some(bin_id) = bin else {
+            let mut items = self.search(query);
+            items.sort_by(|a, b| self.root_sort.compare(a, b));
+            return items;
+        };
+        let sort = self.bins.get(bin_id).map(|b| b.sort).unwrap_or_default();
+        let mut items: Vec<&MediaItem> = self
+            .items
+            .values()
+            .filter(|item| item.matches_text(query))
+            .filter(|item| item.bin.as_ref().is_some_and(|b| self.is_within(b, bin_id)))
+            .collect();
+        items.sort_by(|a, b| sort.compare(a, b));
+        items
+    }
+
+    /// Check whether `bin` is `ancestor` or lies somewhere below it
+    fn is_within(&self, bin: &BinId, ancestor: &BinId) -> bool {
+        let mut current = Some(bin);
+        // Bounded by the bin count in case of a corrupt parent cycle
+        for _ in 0..=self.bins.len() {
+            match current {
+                Some(id) if id == ancestor => return true,
+                Some(id) => current = self.bins.get(id).and_then(|b| b.parent.as_ref()),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    fn check_bin(&self, bin: Option<&BinId>) -> Result<()> {
+        match bin {
+            Some(id) if !self.bins.contains_key(id) => Err(VxError::NotFound("Bin".to_string())),
+            _ => Ok(()),
+        }
+    }
+
     /// Verify all media files exist
     pub fn verify_files(&self) -> Vec<MediaId> {
         self.items
@@ -99,11 +431,126 @@ impl MediaLibrary {
             .map(|(id, _)| id.clone())
             .collect()
     }
+
+    /// Update the offline flag of every item from the file system.
+    /// Returns the items that are offline.
+    pub fn refresh_offline(&mut self) -> Vec<MediaId> {
+        for item in self.items.values_mut() {
+            item.offline = !item.exists();
+        }
+        self.offline_items().map(|item| item.id.clone()).collect()
+    }
+
+    /// Items marked offline
+    pub fn offline_items(&self) -> impl Iterator<Item = &MediaItem> {
+        self.items.values().filter(|item| item.offline)
+    }
+
+    /// Search directories for files that could replace missing media. Candidates
+    /// share the missing file's name and are ranked by size and content fingerprint.
+    pub fn propose_relinks(&self, search_dirs: &[PathBuf]) -> Result<Vec<RelinkProposal>> {
+        let files = scan_directories(search_dirs)?;
+        let mut proposals = Vec::new();
+        for item in self.items.values().filter(|item| !item.exists()) {
+            let Some(name) = item.path.file_name() else {
+                continue;
+            };
+            let name = name.to_string_lossy().to_lowercase();
+            let mut candidates = files
+                .get(&name)
+                .into_iter()
+                .flatten()
+                .map(|path| RelinkCandidate {
+                    path: path.clone(),
+                    confidence: rate_candidate(item, path),
+                })
+                .collect::<Vec<_>>();
+            candidates.sort_by(|a, b| b.confidence.cmp(&a.confidence).then(a.path.cmp(&b.path)));
+            proposals.push(RelinkProposal {
+                media_id: item.id.clone(),
+                candidates,
+            });
+        }
+        Ok(proposals)
+    }
+
+    /// Relink every proposal with a candidate safe to apply automatically (see
+    /// [`RelinkProposal::automatic`]) and mark the rest offline. Returns the items
+    /// left offline.
+    pub fn apply_relinks(&mut self, proposals: &[RelinkProposal]) -> Vec<MediaId> {
+        let mut unresolved = Vec::new();
+        for proposal in proposals {
+            let Some(item) = self.items.get_mut(&proposal.media_id) else {
+                continue;
+            };
+            match proposal.automatic() {
+                Some(candidate) => {
+                    item.path = candidate.path.clone();
+                    item.offline = false;
+                }
+                None => {
+                    item.offline = true;
+                    unresolved.push(item.id.clone());
+                }
+            }
+        }
+        unresolved
+    }
+
+    /// Point a media item at a new file
+    pub fn relink(&mut self, id: &MediaId, path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Err(VxError::NotFound(format!("File {}", path.display())));
+        }
+        let item = self
+            .items
+            .get_mut(id)
+            .ok_or_else(|| VxError::NotFound("Media item".to_string()))?;
+        item.path = path.to_path_buf();
+        item.offline = false;
+        Ok(())
+    }
+
+    /// Relink one item, then relink every other missing item that lived below the
+    /// same old folder to the same place below the new folder, if the file exists
+    /// there. Returns all relinked items.
+    pub fn relink_with_structure(&mut self, id: &MediaId, path: &Path) -> Result<Vec<MediaId>> {
+        let old_path = self
+            .get_item(id)
+            .ok_or_else(|| VxError::NotFound("Media item".to_string()))?
+            .path
+            .clone();
+        self.relink(id, path)?;
+
+        let (old_root, new_root) = moved_roots(&old_path, path);
+        let mut relinked = vec![id.clone()];
+        for item in self.items.values_mut().filter(|item| !item.exists()) {
+            let Ok(rest) = item.path.strip_prefix(&old_root) else {
+                continue;
+            };
+            let candidate = new_root.join(rest);
+            if candidate.is_file() {
+                item.path = candidate;
+                item.offline = false;
+                relinked.push(item.id.clone());
+            }
+        }
+        Ok(relinked)
+    }
+}
+
+/// Oldest import first, then by path so equal timestamps sort the same every time
+fn import_order(a: &MediaItem, b: &MediaItem) -> Ordering {
+    a.imported_at
+        .cmp(&b.imported_at)
+        .then_with(|| a.path.cmp(&b.path))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Resolution;
+    use crate::media::BinSortKey;
     use std::path::PathBuf;
 
     #[test]
@@ -166,4 +613,218 @@ mod tests {
         let results = library.search("vacation");
         assert_eq!(results.len(), 2);
     }
+
+    #[test]
+    fn test_smart_collection_follows_library() {
+        let mut library = MediaLibrary::new();
+        let mut item = MediaItem::new(PathBuf::from("interview_a.mov"), MediaType::Video);
+        item.metadata.codec = Some("ProRes".to_string());
+        item.metadata.resolution = Some(Resolution::UHD_4K);
+        item.add_tag("Interview");
+        item.set_rating(Some(4)).unwrap();
+        let a = library.add_item(item);
+        library.add_item(MediaItem::new(PathBuf::from("broll.mp4"), MediaType::Video));
+
+        let selects = library.add_collection(
+            "4K interview selects".to_string(),
+            MediaQuery::All(vec![
+                MediaQuery::Tag("interview".to_string()),
+                MediaQuery::MinRating(3),
+                MediaQuery::MinResolution(Resolution::UHD_4K),
+                MediaQuery::Codec("prores".to_string()),
+            ]),
+        );
+        assert_eq!(library.collection_items(&selects).unwrap().len(), 1);
+
+        library
+            .get_item_mut(&a)
+            .unwrap()
+            .set_rating(Some(2))
+            .unwrap();
+        assert!(library.collection_items(&selects).unwrap().is_empty());
+
+        assert!(
+            library
+                .get_item_mut(&a)
+                .unwrap()
+                .set_rating(Some(6))
+                .is_err()
+        );
+        assert_eq!(library.search("INTERVIEW").len(), 1);
+        assert_eq!(library.tags(), ["Interview"]);
+    }
+
+    #[test]
+    fn test_relink_moved_folder() {
+        let root = std::env::temp_dir().join(format!("vxutil-relink-{}", uuid::Uuid::new_v4()));
+        let new_dir = root.join("new").join("shoot");
+        std::fs::create_dir_all(new_dir.join("audio")).unwrap();
+        std::fs::write(new_dir.join("a.mov"), b"clip a").unwrap();
+        std::fs::write(new_dir.join("audio").join("b.wav"), b"clip b").unwrap();
+
+        let old_dir = root.join("old").join("shoot");
+        let mut library = MediaLibrary::new();
+        let a = library.add_item(MediaItem::new(old_dir.join("a.mov"), MediaType::Video));
+        let b = library.add_item(MediaItem::new(
+            old_dir.join("audio").join("b.wav"),
+            MediaType::Audio,
+        ));
+        let c = library.add_item(MediaItem::new(old_dir.join("c.mov"), MediaType::Video));
+        assert_eq!(library.refresh_offline().len(), 3);
+
+        let proposals = library.propose_relinks(&[root.join("new")]).unwrap();
+        let proposal = proposals.iter().find(|p| p.media_id == a).unwrap();
+        assert_eq!(proposal.best().unwrap().path, new_dir.join("a.mov"));
+
+        let relinked = library
+            .relink_with_structure(&a, &new_dir.join("a.mov"))
+            .unwrap();
+        assert_eq!(relinked.len(), 2);
+        assert!(library.get_item(&b).unwrap().exists());
+
+        let proposals = library.propose_relinks(&[root.join("new")]).unwrap();
+        assert_eq!(library.apply_relinks(&proposals), vec![c.clone()]);
+        assert!(library.get_item(&c).unwrap().offline);
+        assert_eq!(library.offline_items().count(), 1);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_apply_relinks_needs_matching_size() {
+        let root = std::env::temp_dir().join(format!("vxutil-relink-{}", uuid::Uuid::new_v4()));
+        let card = root.join("card");
+        std::fs::create_dir_all(&card).unwrap();
+        std::fs::write(card.join("C0001.MP4"), b"other footage").unwrap();
+        std::fs::write(card.join("C0002.MP4"), b"same footage").unwrap();
+
+        let mut library = MediaLibrary::new();
+        let mut renamed = MediaItem::new(root.join("old").join("C0001.MP4"), MediaType::Video);
+        renamed.metadata.file_size = 5;
+        let renamed = library.add_item(renamed);
+        let mut moved = MediaItem::new(root.join("old").join("C0002.MP4"), MediaType::Video);
+        moved.metadata.file_size = b"same footage".len() as u64;
+        let moved = library.add_item(moved);
+
+        let proposals = library.propose_relinks(&[root.join("card")]).unwrap();
+        assert_eq!(library.apply_relinks(&proposals), vec![renamed.clone()]);
+        assert!(library.get_item(&renamed).unwrap().offline);
+        assert_eq!(
+            library.get_item(&moved).unwrap().path,
+            card.join("C0002.MP4")
+        );
+
+        assert!(library.propose_relinks(&[root.join("missing")]).is_err());
+
+        // A candidate gone since the scan is rated by name instead of failing
+        let gone = card.join("C0002.MP4");
+        std::fs::remove_file(&gone).unwrap();
+        let item = library.get_item(&moved).unwrap();
+        assert_eq!(
+            rate_candidate(item, &gone),
+            crate::media::MatchConfidence::FileName
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_import_duplicates() {
+        let root = std::env::temp_dir().join(format!("vxutil-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.mov"), b"same clip").unwrap();
+        std::fs::write(root.join("a copy.mov"), b"same clip").unwrap();
+        std::fs::write(root.join("b.mov"), b"other clip").unwrap();
+
+        let mut library = MediaLibrary::new();
+        let first = library
+            .import(root.join("a.mov"), MediaType::Video, DuplicatePolicy::Warn)
+            .unwrap();
+        assert!(first.added && first.duplicates.is_empty());
+
+        let warned = library
+            .import(
+                root.join("a copy.mov"),
+                MediaType::Video,
+                DuplicatePolicy::Warn,
+            )
+            .unwrap();
+        assert!(warned.added);
+        assert_eq!(warned.duplicates, vec![first.id.clone()]);
+
+        let reused = library
+            .import(root.join("a.mov"), MediaType::Video, DuplicatePolicy::Reuse)
+            .unwrap();
+        assert!(!reused.added);
+        assert_eq!(reused.id, first.id);
+        assert_eq!(library.count(), 2);
+
+        let other = library
+            .import(root.join("b.mov"), MediaType::Video, DuplicatePolicy::Reuse)
+            .unwrap();
+        assert!(other.added);
+        let groups = library.duplicate_groups();
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0].contains(&first.id) && groups[0].contains(&warned.id));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_bins() {
+        let mut library = MediaLibrary::new();
+        let footage = library.create_bin("Footage".to_string(), None).unwrap();
+        let day1 = library
+            .create_bin("Day 1".to_string(), Some(&footage))
+            .unwrap();
+
+        let a = library.add_item(MediaItem::new(
+            PathBuf::from("b_roll.mp4"),
+            MediaType::Video,
+        ));
+        let b = library.add_item(MediaItem::new(
+            PathBuf::from("a_roll.mp4"),
+            MediaType::Video,
+        ));
+        let c = library.add_item(MediaItem::new(
+            PathBuf::from("roll_music.mp3"),
+            MediaType::Audio,
+        ));
+        library.move_item(&a, Some(&day1)).unwrap();
+        library.move_item(&b, Some(&day1)).unwrap();
+        library.move_item(&c, Some(&footage)).unwrap();
+
+        let names =
+            |items: Vec<&MediaItem>| items.iter().map(|i| i.name.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            names(library.bin_items(Some(&day1))),
+            ["a_roll.mp4", "b_roll.mp4"]
+        );
+        library
+            .set_bin_sort(Some(&day1), BinSort::new(BinSortKey::Name, true))
+            .unwrap();
+        assert_eq!(
+            names(library.bin_items(Some(&day1))),
+            ["b_roll.mp4", "a_roll.mp4"]
+        );
+
+        assert_eq!(library.search_in_bin("roll", Some(&footage)).len(), 3);
+        assert_eq!(library.search_in_bin("roll", Some(&day1)).len(), 2);
+        library
+            .get_item_mut(&a)
+            .unwrap()
+            .tags
+            .push("Interview".to_string());
+        assert_eq!(
+            names(library.search_in_bin("interview", Some(&footage))),
+            ["b_roll.mp4"]
+        );
+        assert!(library.bin_items(None).is_empty());
+
+        // A bin cannot move below itself
+        assert!(library.move_bin(&footage, Some(&day1)).is_err());
+
+        library.remove_bin(&footage).unwrap();
+        assert_eq!(library.bin_items(None).len(), 1);
+        assert_eq!(library.get_bin(&day1).unwrap().parent, None);
+    }
 }
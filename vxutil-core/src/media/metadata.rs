@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::types::{FrameRate, Resolution};
 
 /// Metadata for a media file
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MediaMetadata {
     /// Duration in seconds (None for images)
     pub duration: Option<f64>,
@@ -28,17 +28,3 @@ pub struct MediaMetadata {
     /// File size in bytes
     pub file_size: u64,
 }
-
-impl Default for MediaMetadata {
-    fn default() -> Self {
-        Self {
-            duration: None,
-            resolution: None,
-            frame_rate: None,
-            codec: None,
-            sample_rate: None,
-            bitrate: None,
-            file_size: 0,
-        }
-    }
-}
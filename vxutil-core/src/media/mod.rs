@@ -1,11 +1,23 @@
 //! Media library and media items
 
+mod bin;
+mod collection;
+mod duplicates;
+mod fingerprint;
+mod image_sequence;
 mod item;
 mod library;
 mod metadata;
+mod relink;
 mod types;
 
+pub use bin::{Bin, BinId, BinSort, BinSortKey};
+pub use collection::{CollectionId, MediaQuery, SmartCollection};
+pub use duplicates::{DuplicatePolicy, ImportResult};
+pub use fingerprint::Fingerprint;
+pub use image_sequence::ImageSequence;
 pub use item::MediaItem;
 pub use library::MediaLibrary;
 pub use metadata::MediaMetadata;
+pub use relink::{MatchConfidence, RelinkCandidate, RelinkProposal};
 pub use types::{MediaId, MediaType};
@@ -0,0 +1,131 @@
+//! Relinking offline media to moved or renamed files
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Fingerprint, MediaId, MediaItem};
+use crate::Result;
+
+/// How closely a candidate file matches the missing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum MatchConfidence {
+    FileName,
+    FileNameAndSize,
+    /// Same name, size and content fingerprint
+    Content,
+}
+
+/// A file that may be the new location of a missing media item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelinkCandidate {
+    pub path: PathBuf,
+    pub confidence: MatchConfidence,
+}
+
+/// Candidate locations for one missing media item, best first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelinkProposal {
+    pub media_id: MediaId,
+    pub candidates: Vec<RelinkCandidate>,
+}
+
+impl RelinkProposal {
+    /// The best candidate, if no other candidate matches as closely
+    pub fn best(&self) -> Option<&RelinkCandidate> {
+        let best = self.candidates.first()?;
+        let tied = self
+            .candidates
+            .get(1)
+            .is_some_and(|c| c.confidence == best.confidence);
+        (!tied).then_some(best)
+    }
+
+    /// The best candidate if it is safe to relink without asking: unambiguous and
+    /// at least the same size. A name match alone is common with camera file names
+    /// such as `C0001.MP4`, so those are left for the user to confirm.
+    pub fn automatic(&self) -> Option<&RelinkCandidate> {
+        self.best()
+            .filter(|c| c.confidence >= MatchConfidence::FileNameAndSize)
+    }
+}
+
+/// Files below the search directories, keyed by lowercase file name.
+/// Symlinked directories are not followed. Fails only if a search directory itself
+/// cannot be read; unreadable folders and entries below it are skipped.
+pub(super) fn scan_directories(dirs: &[PathBuf]) -> Result<HashMap<String, Vec<PathBuf>>> {
+    let mut files: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut pending: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        scan_entries(fs::read_dir(dir)?, &mut files, &mut pending);
+    }
+    while let Some(dir) = pending.pop() {
+        // Permission or network errors in one folder shouldn't stop the search
+        if let Ok(entries) = fs::read_dir(&dir) {
+            scan_entries(entries, &mut files, &mut pending);
+        }
+    }
+    Ok(files)
+}
+
+/// Add the files of one folder to `files` and queue its subfolders
+fn scan_entries(
+    entries: fs::ReadDir,
+    files: &mut HashMap<String, Vec<PathBuf>>,
+    pending: &mut Vec<PathBuf>,
+) {
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            pending.push(entry.path());
+        } else if file_type.is_file() {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            files.entry(name).or_default().push(entry.path());
+        }
+    }
+}
+
+/// Rate a candidate with the same file name as a missing item. A candidate that
+/// cannot be read, e.g. one deleted since the scan, is rated by name alone.
+pub(super) fn rate_candidate(item: &MediaItem, path: &Path) -> MatchConfidence {
+    let Ok(size) = fs::metadata(path).map(|m| m.len()) else {
+        return MatchConfidence::FileName;
+    };
+    let known_size = item
+        .fingerprint
+        .map(|f| f.size)
+        .or((item.metadata.file_size > 0).then_some(item.metadata.file_size));
+    if known_size != Some(size) {
+        return MatchConfidence::FileName;
+    }
+
+    let Some(fingerprint) = item.fingerprint else {
+        return MatchConfidence::FileNameAndSize;
+    };
+    match Fingerprint::compute(path) {
+        Ok(candidate) if candidate == fingerprint => MatchConfidence::Content,
+        Ok(_) => MatchConfidence::FileNameAndSize,
+        Err(_) => MatchConfidence::FileName,
+    }
+}
+
+/// Split a relink from `old` to `new` into the changed leading parts of both paths,
+/// dropping the trailing components they share. E.g. `/mnt/a/shoot/x.mov` moved
+/// to `/media/shoot/x.mov` gives (`/mnt/a`, `/media`).
+pub(super) fn moved_roots(old: &Path, new: &Path) -> (PathBuf, PathBuf) {
+    let old_parts: Vec<_> = old.components().collect();
+    let new_parts: Vec<_> = new.components().collect();
+    let shared = old_parts
+        .iter()
+        .rev()
+        .zip(new_parts.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (
+        old_parts[..old_parts.len() - shared].iter().collect(),
+        new_parts[..new_parts.len() - shared].iter().collect(),
+    )
+}
@@ -0,0 +1,211 @@
+//! Collect files - consolidating a project and its media into one folder
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::Project;
+use crate::media::{ImageSequence, MediaId, MediaItem, MediaType};
+use crate::types::{TimeRange, Timecode};
+
+/// Settings for collecting a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectOptions {
+    /// Folder receiving the project file and the media folder
+    pub destination: PathBuf,
+
+    /// Name of the media folder inside the destination
+    pub media_folder: String,
+
+    /// Trim video and audio files to the ranges used in sequences, keeping this
+    /// much extra media on either side. None copies whole files. Uses of a file
+    /// whose handles do not overlap are written as separate parts.
+    pub trim_handles: Option<Duration>,
+}
+
+impl CollectOptions {
+    pub fn new(destination: PathBuf) -> Self {
+        Self {
+            destination,
+            media_folder: "Media".to_string(),
+            trim_handles: None,
+        }
+    }
+}
+
+/// One media file to collect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectEntry {
+    pub media_id: MediaId,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+
+    /// Source range to keep; None copies the whole file. A file trimmed to several
+    /// ranges has one entry per range, each with its own destination.
+    pub range: Option<TimeRange>,
+}
+
+/// Files written by a collect, built with [`Project::collect_plan`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectPlan {
+    /// Path of the collected project file
+    pub project_path: PathBuf,
+    pub entries: Vec<CollectEntry>,
+
+    /// Offline items; they keep their original paths in the collected project
+    pub missing: Vec<MediaId>,
+}
+
+/// Source ranges used by each media item across all sequences, with handles
+/// added, sorted by start. Uses whose handles overlap or touch are merged into one
+/// range; distant uses of a file stay separate ranges.
+pub(super) fn used_ranges(
+    project: &Project,
+    handles: Duration,
+) -> HashMap<MediaId, Vec<TimeRange>> {
+    let mut uses: HashMap<MediaId, Vec<(Timecode, Timecode)>> = HashMap::new();
+    let clips = project
+        .sequences
+        .iter()
+        .flat_map(|s| s.video_tracks.iter().chain(s.audio_tracks.iter()))
+        .flat_map(|t| t.clips().iter());
+    for clip in clips {
+        let (Some(media_id), Some(span)) =
+            (clip.media_id(), clip.source_span(clip.timeline_range()))
+        else {
+            continue;
+        };
+        let mut end = span.range.end().as_duration() + handles;
+        let duration = project
+            .media_library
+            .get_item(media_id)
+            .and_then(|item| item.duration_seconds())
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+        if let Some(duration) = duration {
+            end = end.min(duration);
+        }
+        let start = span.range.start.as_duration().saturating_sub(handles);
+        uses.entry(media_id.clone())
+            .or_default()
+            .push((Timecode(start), Timecode(end)));
+    }
+
+    uses.into_iter()
+        .map(|(media_id, mut bounds)| {
+            bounds.sort();
+            // Freeze frames use an empty span, so merge bounds rather than ranges
+            let mut merged: Vec<(Timecode, Timecode)> = Vec::new();
+            for (start, end) in bounds {
+                match merged.last_mut() {
+                    Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            let ranges = merged
+                .into_iter()
+                .map(|(start, end)| TimeRange::from_bounds(start, end))
+                .collect();
+            (media_id, ranges)
+        })
+        .collect()
+}
+
+/// File name of one of `count` trimmed parts of a file, e.g. `shot part 2.mov`.
+/// A file trimmed to a single range keeps its name.
+pub(super) fn part_file_name(file_name: &Path, index: usize, count: usize) -> PathBuf {
+    if count <= 1 {
+        return file_name.to_path_buf();
+    }
+    let stem = file_name
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "media".to_string());
+    match file_name.extension() {
+        Some(extension) => format!("{stem} part {}.{}", index + 1, extension.to_string_lossy()),
+        None => format!("{stem} part {}", index + 1),
+    }
+    .into()
+}
+
+/// Only time-based media files can be trimmed; image sequences are copied frame by frame
+pub(super) fn is_trimmable(item: &MediaItem) -> bool {
+    item.image_sequence.is_none() && matches!(item.media_type, MediaType::Video | MediaType::Audio)
+}
+
+/// One entry per existing frame of an image sequence, keeping the frame file names
+/// inside `folder`
+pub(super) fn sequence_entries(
+    item: &MediaItem,
+    sequence: &ImageSequence,
+    folder: &Path,
+) -> Vec<CollectEntry> {
+    sequence
+        .frame_range()
+        .filter(|number| sequence.missing.binary_search(number).is_err())
+        .map(|number| {
+            let file_name = sequence.file_name(number);
+            CollectEntry {
+                media_id: item.id.clone(),
+                source: item.path.with_file_name(&file_name),
+                destination: folder.join(file_name),
+                range: None,
+            }
+        })
+        .collect()
+}
+
+/// File name in `folder` not yet taken (ignoring case), adding " 2", " 3", ... before
+/// the extension on collisions
+pub(super) fn unique_destination(
+    folder: &Path,
+    file_name: &Path,
+    taken: &mut HashSet<String>,
+) -> PathBuf {
+    let stem = file_name
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "media".to_string());
+    let extension = file_name
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned());
+
+    let mut candidate: OsString = file_name.as_os_str().to_owned();
+    let mut counter = 1;
+    while !taken.insert(candidate.to_string_lossy().to_lowercase()) {
+        counter += 1;
+        candidate = match &extension {
+            Some(extension) => format!("{stem} {counter}.{extension}").into(),
+            None => format!("{stem} {counter}").into(),
+        };
+    }
+    folder.join(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_destination() {
+        let folder = Path::new("/out/Media");
+        let mut taken = HashSet::new();
+        assert_eq!(
+            unique_destination(folder, Path::new("a.mov"), &mut taken),
+            folder.join("a.mov")
+        );
+        assert_eq!(
+            unique_destination(folder, Path::new("A.MOV"), &mut taken),
+            folder.join("A 2.MOV")
+        );
+        assert_eq!(
+            unique_destination(folder, Path::new("a.mov"), &mut taken),
+            folder.join("a 3.mov")
+        );
+        assert_eq!(
+            unique_destination(folder, Path::new("notes"), &mut taken),
+            folder.join("notes")
+        );
+    }
+}
@@ -1,7 +1,12 @@
 //! Project management
 
+mod collect;
+mod paths;
+#[allow(clippy::module_inception)]
 mod project;
 mod settings;
 
+pub use collect::{CollectEntry, CollectOptions, CollectPlan};
+pub use paths::{PathRemap, make_relative, resolve};
 pub use project::Project;
 pub use settings::ProjectSettings;
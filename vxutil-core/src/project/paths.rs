@@ -0,0 +1,123 @@
+//! Portable media paths
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Rewrites paths below one root to another, e.g. `/mnt/nas` to `/Volumes/nas`
+/// when opening a project on a machine that mounts storage elsewhere
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathRemap {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl PathRemap {
+    pub fn new(from: impl Into<PathBuf>, to: impl Into<PathBuf>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// The remapped path, if `path` lies below `from`
+    pub fn apply(&self, path: &Path) -> Option<PathBuf> {
+        let rest = path.strip_prefix(&self.from).ok()?;
+        Some(self.to.join(rest))
+    }
+}
+
+/// Express `path` relative to `base` (which may need `..` steps). Paths that share
+/// nothing with `base` beyond the file system root, and relative paths, are
+/// returned unchanged.
+pub fn make_relative(path: &Path, base: &Path) -> PathBuf {
+    if path.is_relative() || base.is_relative() {
+        return path.to_path_buf();
+    }
+
+    let path_parts: Vec<Component> = path.components().collect();
+    let base_parts: Vec<Component> = base.components().collect();
+    let shared = path_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let shares_folder = path_parts[..shared]
+        .iter()
+        .any(|c| matches!(c, Component::Normal(_)));
+    if !shares_folder {
+        return path.to_path_buf();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in shared..base_parts.len() {
+        relative.push("..");
+    }
+    relative.extend(&path_parts[shared..]);
+    relative
+}
+
+/// Resolve a stored path against `base`, then apply the first matching remap rule
+pub fn resolve(path: &Path, base: &Path, remaps: &[PathRemap]) -> PathBuf {
+    let absolute = if path.is_relative() {
+        normalize(&base.join(path))
+    } else {
+        path.to_path_buf()
+    };
+    remaps
+        .iter()
+        .find_map(|remap| remap.apply(&absolute))
+        .unwrap_or(absolute)
+}
+
+/// Fold `..` and `.` components without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_round_trip() {
+        let base = Path::new("/projects/doc");
+        for (path, stored) in [
+            ("/projects/doc/media/a.mov", "media/a.mov"),
+            ("/projects/shared/b.wav", "../shared/b.wav"),
+            ("/mnt/nas/c.mov", "/mnt/nas/c.mov"),
+        ] {
+            let relative = make_relative(Path::new(path), base);
+            assert_eq!(relative, Path::new(stored));
+            assert_eq!(resolve(&relative, base, &[]), Path::new(path));
+        }
+    }
+
+    #[test]
+    fn test_remap() {
+        let remaps = [PathRemap::new("/mnt/nas", "/Volumes/nas")];
+        assert_eq!(
+            resolve(Path::new("/mnt/nas/c.mov"), Path::new("/projects"), &remaps),
+            Path::new("/Volumes/nas/c.mov")
+        );
+        assert_eq!(
+            resolve(
+                Path::new("/mnt/other/c.mov"),
+                Path::new("/projects"),
+                &remaps
+            ),
+            Path::new("/mnt/other/c.mov")
+        );
+    }
+}
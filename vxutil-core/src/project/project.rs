@@ -1,17 +1,35 @@
 //! Project management
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use super::ProjectSettings;
-use crate::Result;
+use super::collect::{
+    is_trimmable, part_file_name, sequence_entries, unique_destination, used_ranges,
+};
+use super::{
+    CollectEntry, CollectOptions, CollectPlan, PathRemap, ProjectSettings, make_relative, resolve,
+};
+use crate::media::{Fingerprint, MediaId, MediaItem, MediaLibrary, MediaType};
+use crate::timeline::{
+    Clip, ClipId, ClipSource, EditMode, Sequence, SequenceClipboard, SequenceId, TrackType,
+};
+use crate::types::{TimeRange, Timecode};
+use crate::{Result, VxError};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub name: String,
     pub path: PathBuf,
     pub settings: ProjectSettings,
-    // TODO: sequences, media library
+
+    #[serde(default)]
+    pub sequences: Vec<Sequence>,
+
+    #[serde(default)]
+    pub media_library: MediaLibrary,
 }
 
 impl Project {
@@ -20,16 +38,773 @@ impl Project {
             name,
             path,
             settings,
+            sequences: Vec::new(),
+            media_library: MediaLibrary::new(),
         }
     }
 
-    pub fn load(_path: &PathBuf) -> Result<Self> {
-        // TODO: Implement project loading
-        todo!("Project loading not yet implemented")
+    /// Load a project file. Relative media paths are resolved against the project folder.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_with_remaps(path, &[])
     }
 
+    /// Load a project file, rewriting media paths with the first matching remap rule
+    pub fn load_with_remaps(path: &Path, remaps: &[PathRemap]) -> Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let mut project: Project = serde_json::from_str(&json)?;
+        project.path = path.to_path_buf();
+
+        let base = project.base_dir();
+        for item in project.media_library.items_mut() {
+            item.path = resolve(&item.path, &base, remaps);
+        }
+        Ok(project)
+    }
+
+    /// Save to the project path. Media paths are stored relative to the project
+    /// folder where possible, so the project can move together with its media.
     pub fn save(&self) -> Result<()> {
-        // TODO: Implement project saving
-        todo!("Project saving not yet implemented")
+        let base = self.base_dir();
+        let mut stored = self.clone();
+        for item in stored.media_library.items_mut() {
+            item.path = make_relative(&item.path, &base);
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&stored)?)?;
+        Ok(())
+    }
+
+    /// Absolute folder containing the project file
+    pub fn base_dir(&self) -> PathBuf {
+        std::path::absolute(&self.path)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .unwrap_or_default()
+    }
+
+    /// Work out where each media file goes when collecting the project into
+    /// `options.destination`. Media not used by any clip is copied whole and image
+    /// sequences are copied frame by frame into a folder of their own. When trimming,
+    /// distant uses of a file are written as separate parts.
+    pub fn collect_plan(&self, options: &CollectOptions) -> CollectPlan {
+        let media_dir = options.destination.join(&options.media_folder);
+        let ranges = options
+            .trim_handles
+            .map(|handles| used_ranges(self, handles))
+            .unwrap_or_default();
+
+        // Sort so collisions are numbered the same way on every run
+        let mut items: Vec<_> = self.media_library.items().collect();
+        items.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut taken = HashSet::new();
+        let mut entries = Vec::new();
+        let mut missing = Vec::new();
+        for item in items {
+            if !item.exists() {
+                missing.push(item.id.clone());
+                continue;
+            }
+            if let Some(sequence) = &item.image_sequence {
+                // Frames go into their own folder, named after the one they came from,
+                // so the frame file names stay intact
+                let folder_name = item
+                    .path
+                    .parent()
+                    .and_then(Path::file_name)
+                    .map_or(Path::new(&item.name), Path::new);
+                let folder = unique_destination(&media_dir, folder_name, &mut taken);
+                entries.extend(sequence_entries(item, sequence, &folder));
+                continue;
+            }
+            let file_name = item
+                .path
+                .file_name()
+                .map_or(Path::new(&item.name), Path::new);
+            let parts = match ranges.get(&item.id) {
+                Some(parts) if is_trimmable(item) => parts.iter().copied().map(Some).collect(),
+                _ => vec![None],
+            };
+            for (index, range) in parts.iter().enumerate() {
+                let part_name = part_file_name(file_name, index, parts.len());
+                entries.push(CollectEntry {
+                    media_id: item.id.clone(),
+                    source: item.path.clone(),
+                    destination: unique_destination(&media_dir, &part_name, &mut taken),
+                    range: *range,
+                });
+            }
+        }
+
+        let file_name = self
+            .path
+            .file_name()
+            .map_or_else(|| format!("{}.vxp", self.name).into(), |n| n.to_owned());
+        CollectPlan {
+            project_path: options.destination.join(file_name),
+            entries,
+            missing,
+        }
+    }
+
+    /// Copy the project and its media as laid out in `plan`, then save the copy with
+    /// paths pointing at the collected media. Entries with a range are written by
+    /// `trim`, which returns the source time the trimmed file starts at (stream
+    /// copies start at the keyframe before the range). The first part of a trimmed
+    /// file stays with its media item and each further part becomes an item of its
+    /// own; clips are pointed at the part holding their source range, with source
+    /// times shifted to match. Returns the collected project.
+    pub fn collect(
+        &self,
+        plan: &CollectPlan,
+        mut trim: impl FnMut(&Path, &Path, TimeRange) -> Result<Timecode>,
+    ) -> Result<Project> {
+        let mut collected = self.clone();
+        collected.path = plan.project_path.clone();
+
+        // Trimmed parts of each original item: planned range, item and start time
+        let mut parts: HashMap<MediaId, Vec<(TimeRange, MediaId, Duration)>> = HashMap::new();
+        for entry in plan.entries.iter() {
+            if let Some(parent) = entry.destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let library = &mut collected.media_library;
+            let original = library
+                .get_item(&entry.media_id)
+                .ok_or_else(|| VxError::NotFound("Media item".to_string()))?;
+            let Some(range) = entry.range else {
+                fs::copy(&entry.source, &entry.destination)?;
+                // Image sequences have an entry per frame; the item points at the first
+                if entry.source == original.path
+                    && let Some(item) = library.get_item_mut(&entry.media_id)
+                {
+                    item.path = entry.destination.clone();
+                }
+                continue;
+            };
+
+            let start = trim(&entry.source, &entry.destination, range)?;
+            let mut item = original.clone();
+            if parts.contains_key(&entry.media_id) {
+                item.id = MediaId::new();
+                if let Some(name) = entry.destination.file_name() {
+                    item.name = name.to_string_lossy().into_owned();
+                }
+            }
+            item.path = entry.destination.clone();
+            item.metadata.duration = Some((range.end().as_seconds() - start.as_seconds()).max(0.0));
+            item.metadata.file_size = fs::metadata(&entry.destination)?.len();
+            item.fingerprint = Some(Fingerprint::compute(&entry.destination)?);
+            // Replaces the original item for the first part
+            let item_id = library.add_item(item);
+            parts.entry(entry.media_id.clone()).or_default().push((
+                range,
+                item_id,
+                start.as_duration(),
+            ));
+        }
+
+        let tracks = collected
+            .sequences
+            .iter_mut()
+            .flat_map(|s| s.video_tracks.iter_mut().chain(s.audio_tracks.iter_mut()));
+        for track in tracks {
+            track.update_clips(|clip| {
+                let (Some(media_parts), Some(span)) = (
+                    clip.media_id().and_then(|id| parts.get(id)),
+                    clip.source_span(clip.timeline_range()),
+                ) else {
+                    return;
+                };
+                let part = media_parts
+                    .iter()
+                    .find(|(range, ..)| range.contains_range(span.range))
+                    .unwrap_or(&media_parts[0]);
+                let (_, item_id, offset) = part;
+                clip.source = ClipSource::Media(item_id.clone());
+                clip.source_in = Timecode(clip.source_in.as_duration().saturating_sub(*offset));
+                clip.source_out = Timecode(clip.source_out.as_duration().saturating_sub(*offset));
+            });
+        }
+
+        if let Some(parent) = plan.project_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        collected.save()?;
+        Ok(collected)
+    }
+
+    /// Merge duplicate media items into `survivor`: clips using a duplicate are
+    /// redirected to the survivor, which also takes over their tags, and the
+    /// duplicates are removed from the library. Returns the number of clips redirected.
+    pub fn merge_media(&mut self, survivor: &MediaId, duplicates: &[MediaId]) -> Result<usize> {
+        if duplicates.contains(survivor) {
+            return Err(VxError::InvalidParameter(
+                "Cannot merge a media item into itself".to_string(),
+            ));
+        }
+        let library = &mut self.media_library;
+        if library.get_item(survivor).is_none()
+            || duplicates.iter().any(|id| library.get_item(id).is_none())
+        {
+            return Err(VxError::NotFound("Media item".to_string()));
+        }
+
+        let removed: Vec<MediaItem> = duplicates
+            .iter()
+            .filter_map(|id| library.remove_item(id))
+            .collect();
+        if let Some(item) = library.get_item_mut(survivor) {
+            for tag in removed.iter().flat_map(|item| item.tags.iter()) {
+                item.add_tag(tag);
+            }
+        }
+
+        let tracks = self
+            .sequences
+            .iter_mut()
+            .flat_map(|s| s.video_tracks.iter_mut().chain(s.audio_tracks.iter_mut()));
+        let mut redirected = 0;
+        for track in tracks {
+            track.update_clips(|clip| {
+                if let ClipSource::Media(id) = &mut clip.source
+                    && duplicates.contains(id)
+                {
+                    *id = survivor.clone();
+                    redirected += 1;
+                }
+            });
+        }
+        Ok(redirected)
+    }
+
+    /// New clip for a media item at `position`: stills last the default still
+    /// duration, other media is used whole
+    pub fn clip_for_media(&self, media_id: &MediaId, position: Timecode) -> Result<Clip> {
+        let item = self
+            .media_library
+            .get_item(media_id)
+            .ok_or_else(|| VxError::NotFound("Media item".to_string()))?;
+        if item.media_type == MediaType::Image {
+            return Ok(Clip::new_still(
+                item.name.clone(),
+                media_id.clone(),
+                position,
+                self.settings.still_duration,
+            ));
+        }
+        let duration = item.duration_seconds().ok_or_else(|| {
+            VxError::InvalidParameter(format!("Duration of '{}' is unknown", item.name))
+        })?;
+        Ok(Clip::new(
+            item.name.clone(),
+            media_id.clone(),
+            position,
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(duration),
+        ))
+    }
+
+    /// Add a sequence to the project
+    pub fn add_sequence(&mut self, sequence: Sequence) -> SequenceId {
+        let id = sequence.id.clone();
+        self.sequences.push(sequence);
+        id
+    }
+
+    /// Get sequence by ID
+    pub fn get_sequence(&self, id: &SequenceId) -> Option<&Sequence> {
+        self.sequences.iter().find(|s| &s.id == id)
+    }
+
+    /// Get mutable sequence by ID
+    pub fn get_sequence_mut(&mut self, id: &SequenceId) -> Option<&mut Sequence> {
+        self.sequences.iter_mut().find(|s| &s.id == id)
+    }
+
+    /// Check that `child` can be nested inside `parent` without creating a cycle
+    pub fn check_nesting(&self, parent: &SequenceId, child: &SequenceId) -> Result<()> {
+        // Walk everything reachable from the child; reaching the parent means a cycle
+        let mut visited = HashSet::new();
+        let mut pending = vec![child.clone()];
+        while let Some(id) = pending.pop() {
+            if &id == parent {
+                return Err(VxError::Timeline(
+                    "Nesting would create a sequence cycle".to_string(),
+                ));
+            }
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+            let sequence = self
+                .get_sequence(&id)
+                .ok_or_else(|| VxError::NotFound("Nested sequence".to_string()))?;
+            pending.extend(sequence.nested_sequence_ids());
+        }
+        Ok(())
+    }
+
+    /// New compound clip at `position` showing the whole of a sequence
+    pub fn clip_for_sequence(&self, sequence_id: &SequenceId, position: Timecode) -> Result<Clip> {
+        let sequence = self
+            .get_sequence(sequence_id)
+            .ok_or_else(|| VxError::NotFound("Sequence".to_string()))?;
+        Ok(Clip::new_compound(
+            sequence.name.clone(),
+            sequence_id.clone(),
+            position,
+            sequence.duration().as_duration(),
+        ))
+    }
+
+    /// Place clips on the targeted tracks of a sequence, see [`Sequence::edit`].
+    /// Fails without changes if a compound clip would nest a sequence inside itself.
+    pub fn edit(
+        &mut self,
+        sequence_id: &SequenceId,
+        clips: Vec<(TrackType, Clip)>,
+        at: Timecode,
+        mode: EditMode,
+    ) -> Result<Vec<ClipId>> {
+        self.check_compound_clips(sequence_id, clips.iter().map(|(_, clip)| clip))?;
+        self.get_sequence_mut(sequence_id)
+            .ok_or_else(|| VxError::NotFound("Sequence".to_string()))?
+            .edit(clips, at, mode)
+    }
+
+    /// Paste copied clips into a sequence, see [`Sequence::paste`]. Fails without
+    /// changes if a compound clip would nest a sequence inside itself.
+    pub fn paste(
+        &mut self,
+        sequence_id: &SequenceId,
+        clipboard: &SequenceClipboard,
+        at: Timecode,
+    ) -> Result<Vec<ClipId>> {
+        let clips = clipboard.tracks.iter().flat_map(|t| t.clips.iter());
+        self.check_compound_clips(sequence_id, clips)?;
+        self.get_sequence_mut(sequence_id)
+            .ok_or_else(|| VxError::NotFound("Sequence".to_string()))?
+            .paste(clipboard, at)
+    }
+
+    /// Check every compound clip among `clips` with [`Project::check_nesting`]
+    fn check_compound_clips<'a>(
+        &self,
+        parent: &SequenceId,
+        clips: impl Iterator<Item = &'a Clip>,
+    ) -> Result<()> {
+        for nested in clips.filter_map(|clip| clip.nested_sequence()) {
+            self.check_nesting(parent, nested)?;
+        }
+        Ok(())
+    }
+
+    /// Move clips of a sequence into a new sequence and replace them with a compound clip
+    pub fn nest_clips(
+        &mut self,
+        sequence_id: &SequenceId,
+        clip_ids: &[ClipId],
+        name: String,
+    ) -> Result<ClipId> {
+        let sequence = self
+            .get_sequence_mut(sequence_id)
+            .ok_or_else(|| VxError::NotFound("Sequence".to_string()))?;
+        let (nested, compound_id) = sequence.nest_clips(clip_ids, name)?;
+        self.add_sequence(nested);
+        Ok(compound_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::{Track, TrackId, TrackType};
+    use crate::types::FrameRate;
+
+    #[test]
+    fn test_nesting_cycle_detection() {
+        let mut project = Project::new(
+            "test".to_string(),
+            PathBuf::from("project.vxp"),
+            ProjectSettings::default(),
+        );
+        let settings = project.settings.clone();
+        let outer = Sequence::new(
+            "outer".to_string(),
+            settings.frame_rate,
+            settings.resolution,
+        );
+        let mut inner = Sequence::new(
+            "inner".to_string(),
+            settings.frame_rate,
+            settings.resolution,
+        );
+
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(Clip::new_compound(
+            "outer".to_string(),
+            outer.id.clone(),
+            Timecode::from_seconds(0.0),
+            Duration::from_secs(1),
+        ));
+        inner.add_track(track);
+
+        let outer_id = project.add_sequence(outer);
+        let inner_id = project.add_sequence(inner);
+
+        // inner already contains outer, so outer cannot contain inner
+        assert!(project.check_nesting(&outer_id, &inner_id).is_err());
+        assert!(project.check_nesting(&inner_id, &outer_id).is_ok());
+        assert!(project.check_nesting(&outer_id, &outer_id).is_err());
+
+        // Placing a compound clip goes through the same check
+        let compound = project
+            .clip_for_sequence(&inner_id, Timecode::from_seconds(0.0))
+            .unwrap();
+        assert_eq!(compound.timeline_duration(), Duration::from_secs(1));
+        let target = |project: &mut Project, id: &SequenceId| {
+            let sequence = project.get_sequence_mut(id).unwrap();
+            let track = sequence.new_track(TrackType::Video, "V1".to_string());
+            sequence.target_track(track).unwrap();
+        };
+        let other = project.add_sequence(Sequence::new(
+            "other".to_string(),
+            settings.frame_rate,
+            settings.resolution,
+        ));
+        target(&mut project, &outer_id);
+        target(&mut project, &other);
+        let at = Timecode::from_seconds(0.0);
+        let edit = vec![(TrackType::Video, compound)];
+        assert!(
+            project
+                .edit(&outer_id, edit.clone(), at, EditMode::Overwrite)
+                .is_err()
+        );
+        assert!(
+            project
+                .get_sequence(&outer_id)
+                .unwrap()
+                .nested_sequence_ids()
+                .is_empty()
+        );
+        project.edit(&other, edit, at, EditMode::Overwrite).unwrap();
+
+        let inner = project.get_sequence(&inner_id).unwrap();
+        let clipboard = inner.copy_range(TimeRange::new(
+            Timecode::from_seconds(0.0),
+            Duration::from_secs(1),
+        ));
+        assert!(project.paste(&outer_id, &clipboard, at).is_err());
+        project.paste(&other, &clipboard, at).unwrap();
+    }
+
+    #[test]
+    fn test_save_load_portable_paths() {
+        let root = std::env::temp_dir().join(format!("vxutil-project-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let mut project = Project::new(
+            "doc".to_string(),
+            root.join("doc.vxp"),
+            ProjectSettings::default(),
+        );
+        let local = project.media_library.add_item(MediaItem::new(
+            root.join("media").join("a.mov"),
+            MediaType::Video,
+        ));
+        let nas = project.media_library.add_item(MediaItem::new(
+            PathBuf::from("/mnt/nas/b.mov"),
+            MediaType::Video,
+        ));
+        project.save().unwrap();
+
+        let json = fs::read_to_string(root.join("doc.vxp")).unwrap();
+        assert!(json.contains(&serde_json::to_string(&Path::new("media").join("a.mov")).unwrap()));
+
+        // Moved project folder plus a different NAS mount point
+        let moved = root.with_extension("moved");
+        fs::rename(&root, &moved).unwrap();
+        let loaded = Project::load_with_remaps(
+            &moved.join("doc.vxp"),
+            &[PathRemap::new("/mnt/nas", "/Volumes/nas")],
+        )
+        .unwrap();
+        assert_eq!(
+            loaded.media_library.get_item(&local).unwrap().path,
+            moved.join("media").join("a.mov")
+        );
+        assert_eq!(
+            loaded.media_library.get_item(&nas).unwrap().path,
+            Path::new("/Volumes/nas/b.mov")
+        );
+
+        fs::remove_dir_all(moved).unwrap();
+    }
+
+    #[test]
+    fn test_collect_trims_used_ranges() {
+        let root = std::env::temp_dir().join(format!("vxutil-collect-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("a").join("shot.mov"), b"first").unwrap();
+        fs::write(root.join("b").join("shot.mov"), b"second").unwrap();
+
+        let mut project = Project::new(
+            "doc".to_string(),
+            root.join("doc.vxp"),
+            ProjectSettings::default(),
+        );
+        let mut item = MediaItem::new(root.join("a").join("shot.mov"), MediaType::Video);
+        item.metadata.duration = Some(60.0);
+        let trimmed = project.media_library.add_item(item);
+        let copied = project.media_library.add_item(MediaItem::new(
+            root.join("b").join("shot.mov"),
+            MediaType::Video,
+        ));
+
+        let settings = project.settings.clone();
+        let mut sequence =
+            Sequence::new("main".to_string(), settings.frame_rate, settings.resolution);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(Clip::new(
+            "shot".to_string(),
+            trimmed.clone(),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(10.0),
+            Timecode::from_seconds(20.0),
+        ));
+        sequence.add_track(track);
+        project.add_sequence(sequence);
+
+        let mut options = CollectOptions::new(root.join("collected"));
+        options.trim_handles = Some(Duration::from_secs(2));
+        let plan = project.collect_plan(&options);
+        assert!(plan.missing.is_empty());
+        assert_eq!(plan.project_path, root.join("collected").join("doc.vxp"));
+
+        let entry = |id: &MediaId| plan.entries.iter().find(|e| &e.media_id == id).unwrap();
+        assert_eq!(
+            entry(&trimmed).range,
+            Some(TimeRange::from_bounds(
+                Timecode::from_seconds(8.0),
+                Timecode::from_seconds(22.0)
+            ))
+        );
+        assert_eq!(entry(&copied).range, None);
+        assert_ne!(entry(&trimmed).destination, entry(&copied).destination);
+
+        // Pretend the stream copy had to start at a keyframe at 7s
+        let collected = project
+            .collect(&plan, |_, destination, _| {
+                fs::write(destination, b"trimmed")?;
+                Ok(Timecode::from_seconds(7.0))
+            })
+            .unwrap();
+        let clip = &collected.sequences[0].video_tracks[0].clips()[0];
+        assert_eq!(clip.source_in, Timecode::from_seconds(3.0));
+        assert_eq!(clip.source_out, Timecode::from_seconds(13.0));
+
+        let loaded = Project::load(&plan.project_path).unwrap();
+        let copied_path = &loaded.media_library.get_item(&copied).unwrap().path;
+        assert_eq!(fs::read(copied_path).unwrap(), b"second");
+        assert!(copied_path.starts_with(root.join("collected").join("Media")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_collect_trims_distant_uses_separately() {
+        let root = std::env::temp_dir().join(format!("vxutil-collect-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("interview.mov"), b"an hour of footage").unwrap();
+
+        let mut project = Project::new(
+            "doc".to_string(),
+            root.join("doc.vxp"),
+            ProjectSettings::default(),
+        );
+        let mut item = MediaItem::new(root.join("interview.mov"), MediaType::Video);
+        item.metadata.duration = Some(3600.0);
+        let media_id = project.media_library.add_item(item);
+
+        // Uses at 0:10 and 0:25 share handles, the one at 59:00 stands alone
+        let settings = project.settings.clone();
+        let mut sequence =
+            Sequence::new("main".to_string(), settings.frame_rate, settings.resolution);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        for (position, source_in) in [(0.0, 10.0), (10.0, 3540.0), (20.0, 25.0)] {
+            track.add_clip(Clip::new(
+                "interview".to_string(),
+                media_id.clone(),
+                Timecode::from_seconds(position),
+                Timecode::from_seconds(source_in),
+                Timecode::from_seconds(source_in + 5.0),
+            ));
+        }
+        sequence.add_track(track);
+        project.add_sequence(sequence);
+
+        let mut options = CollectOptions::new(root.join("collected"));
+        options.trim_handles = Some(Duration::from_secs(5));
+        let plan = project.collect_plan(&options);
+        let media_dir = root.join("collected").join("Media");
+        let parts: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| (e.destination.clone(), e.range.unwrap()))
+            .collect();
+        let range = |start: f64, end: f64| {
+            TimeRange::from_bounds(Timecode::from_seconds(start), Timecode::from_seconds(end))
+        };
+        assert_eq!(
+            parts,
+            vec![
+                (media_dir.join("interview part 1.mov"), range(5.0, 35.0)),
+                (
+                    media_dir.join("interview part 2.mov"),
+                    range(3535.0, 3550.0)
+                ),
+            ]
+        );
+
+        let collected = project
+            .collect(&plan, |_, destination, range| {
+                fs::write(destination, b"trimmed")?;
+                Ok(range.start)
+            })
+            .unwrap();
+        assert_eq!(collected.media_library.count(), 2);
+        let clips = collected.sequences[0].video_tracks[0].clips();
+        let source = |clip: &Clip| {
+            let item = collected.media_library.get_item(clip.media_id().unwrap());
+            let path = item.unwrap().path.clone();
+            (path, clip.source_in, clip.source_out)
+        };
+        let part = |number: u32| media_dir.join(format!("interview part {number}.mov"));
+        assert_eq!(
+            clips.iter().map(source).collect::<Vec<_>>(),
+            vec![
+                (
+                    part(1),
+                    Timecode::from_seconds(5.0),
+                    Timecode::from_seconds(10.0)
+                ),
+                (
+                    part(2),
+                    Timecode::from_seconds(5.0),
+                    Timecode::from_seconds(10.0)
+                ),
+                (
+                    part(1),
+                    Timecode::from_seconds(20.0),
+                    Timecode::from_seconds(25.0)
+                ),
+            ]
+        );
+        assert_eq!(clips[0].media_id(), Some(&media_id));
+        let second = collected
+            .media_library
+            .get_item(clips[1].media_id().unwrap())
+            .unwrap();
+        assert_eq!(second.name, "interview part 2.mov");
+        assert_eq!(second.duration_seconds(), Some(15.0));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_collect_image_sequence() {
+        let root = std::env::temp_dir().join(format!("vxutil-collect-{}", uuid::Uuid::new_v4()));
+        let frames = root.join("comp_v003");
+        fs::create_dir_all(&frames).unwrap();
+        for number in [1, 2, 4] {
+            fs::write(frames.join(format!("shot_{number:04}.png")), b"frame").unwrap();
+        }
+
+        let mut project = Project::new(
+            "doc".to_string(),
+            root.join("doc.vxp"),
+            ProjectSettings::default(),
+        );
+        let id = project
+            .media_library
+            .import_image_sequence(&frames.join("shot_0001.png"), FrameRate::FPS_24)
+            .unwrap();
+
+        let mut options = CollectOptions::new(root.join("collected"));
+        options.trim_handles = Some(Duration::from_secs(1));
+        let plan = project.collect_plan(&options);
+        let folder = root.join("collected").join("Media").join("comp_v003");
+        let destinations: Vec<_> = plan.entries.iter().map(|e| e.destination.clone()).collect();
+        assert_eq!(
+            destinations,
+            ["shot_0001.png", "shot_0002.png", "shot_0004.png"].map(|name| folder.join(name))
+        );
+        assert!(plan.entries.iter().all(|e| e.range.is_none()));
+
+        let collected = project
+            .collect(&plan, |_, _, _| panic!("sequences are never trimmed"))
+            .unwrap();
+        let item = collected.media_library.get_item(&id).unwrap();
+        assert_eq!(item.path, folder.join("shot_0001.png"));
+        assert!(folder.join("shot_0004.png").is_file());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_merge_media() {
+        let mut project = Project::new(
+            "doc".to_string(),
+            PathBuf::from("doc.vxp"),
+            ProjectSettings::default(),
+        );
+        let survivor = project
+            .media_library
+            .add_item(MediaItem::new(PathBuf::from("a.mov"), MediaType::Video));
+        let mut copy = MediaItem::new(PathBuf::from("a copy.mov"), MediaType::Video);
+        copy.add_tag("interview");
+        let copy = project.media_library.add_item(copy);
+
+        let settings = project.settings.clone();
+        let mut sequence =
+            Sequence::new("main".to_string(), settings.frame_rate, settings.resolution);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        for (index, media) in [&survivor, &copy, &copy].into_iter().enumerate() {
+            track.add_clip(Clip::new(
+                "shot".to_string(),
+                media.clone(),
+                Timecode::from_seconds(index as f64),
+                Timecode::from_seconds(0.0),
+                Timecode::from_seconds(1.0),
+            ));
+        }
+        sequence.add_track(track);
+        project.add_sequence(sequence);
+
+        assert!(
+            project
+                .merge_media(&survivor, std::slice::from_ref(&survivor))
+                .is_err()
+        );
+        assert_eq!(
+            project
+                .merge_media(&survivor, std::slice::from_ref(&copy))
+                .unwrap(),
+            2
+        );
+        assert!(project.media_library.get_item(&copy).is_none());
+        assert!(
+            project
+                .media_library
+                .get_item(&survivor)
+                .unwrap()
+                .has_tag("interview")
+        );
+        assert!(
+            project.sequences[0].video_tracks[0]
+                .clips()
+                .iter()
+                .all(|c| c.media_id() == Some(&survivor))
+        );
     }
 }
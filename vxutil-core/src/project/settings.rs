@@ -1,6 +1,7 @@
 //! Project settings
 
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 use crate::types::{FrameRate, Resolution};
 
@@ -9,6 +10,10 @@ pub struct ProjectSettings {
     pub frame_rate: FrameRate,
     pub resolution: Resolution,
     pub sample_rate: u32, // Audio sample rate (e.g., 48000)
+
+    /// Length of still image clips when first added to a sequence
+    #[serde(default = "default_still_duration")]
+    pub still_duration: Duration,
 }
 
 impl Default for ProjectSettings {
@@ -17,6 +22,11 @@ impl Default for ProjectSettings {
             frame_rate: FrameRate::FPS_30,
             resolution: Resolution::FULL_HD,
             sample_rate: 48000,
+            still_duration: default_still_duration(),
         }
     }
 }
+
+fn default_still_duration() -> Duration {
+    Duration::from_secs(5)
+}
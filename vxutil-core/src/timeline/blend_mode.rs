@@ -3,8 +3,9 @@
 use serde::{Deserialize, Serialize};
 
 /// Blend mode for compositing clips
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum BlendMode {
+    #[default]
     Normal,
     Multiply,
     Screen,
@@ -14,9 +15,3 @@ pub enum BlendMode {
     Darken,
     Lighten,
 }
-
-impl Default for BlendMode {
-    fn default() -> Self {
-        BlendMode::Normal
-    }
-}
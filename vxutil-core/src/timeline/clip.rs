@@ -4,10 +4,12 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use uuid::Uuid;
 
-use super::BlendMode;
-use crate::types::{TimeRange, Timecode};
-use crate::media::MediaId;
+use super::{BlendMode, Marker, RetimeMode, ScaleMode, SequenceId, TimeRemap};
 use crate::effects::EffectType;
+use crate::generators::Generator;
+use crate::media::MediaId;
+use crate::types::{TimeRange, Timecode};
+use crate::{Result, VxError};
 
 /// Unique identifier for a clip
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -25,14 +27,34 @@ impl Default for ClipId {
     }
 }
 
+/// Where a clip gets its frames from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClipSource {
+    /// A media item in the library
+    Media(MediaId),
+    /// Synthesized content with no source media (titles, mattes)
+    Generator(Generator),
+    /// Another sequence, nested as a compound clip
+    Sequence(SequenceId),
+}
+
+/// Part of the source media played during a span of timeline time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceSpan {
+    pub range: TimeRange,
+
+    /// The range plays from its end towards its start (audio samples must be reversed)
+    pub reversed: bool,
+}
+
 /// A clip represents a piece of media placed on the timeline
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Clip {
     pub id: ClipId,
     pub name: String,
 
-    /// Reference to the source media
-    pub source_media: MediaId,
+    /// Source of the clip's content
+    pub source: ClipSource,
 
     /// Position on the timeline where this clip starts
     pub timeline_position: Timecode,
@@ -41,14 +63,32 @@ pub struct Clip {
     pub source_in: Timecode,
     pub source_out: Timecode,
 
-    /// Speed multiplier (1.0 = normal, 2.0 = 2x speed, 0.5 = half speed)
-    pub speed: f64,
+    /// Speed multiplier (1.0 = normal, 2.0 = 2x speed, 0.5 = half speed).
+    /// Negative values play the source backwards from `source_out`; zero is invalid.
+    speed: f64,
+
+    /// Variable speed curve. When set it replaces `speed`, the clip lasts the
+    /// remap's duration and `source_in` is the source position at the clip start.
+    #[serde(default)]
+    pub time_remap: Option<TimeRemap>,
+
+    /// How frames are picked when the clip is not played at normal speed
+    #[serde(default)]
+    pub retime_mode: RetimeMode,
 
     /// Blend mode for compositing
     pub blend_mode: BlendMode,
 
+    /// How frames of a different size are scaled to the sequence resolution
+    #[serde(default)]
+    pub scale_mode: ScaleMode,
+
     /// List of effects applied to this clip
     pub effects: Vec<EffectType>,
+
+    /// Markers, relative to the clip start
+    #[serde(default)]
+    pub markers: Vec<Marker>,
 }
 
 impl Clip {
@@ -62,20 +102,148 @@ impl Clip {
         Self {
             id: ClipId::new(),
             name,
-            source_media,
+            source: ClipSource::Media(source_media),
             timeline_position,
             source_in,
             source_out,
             speed: 1.0,
+            time_remap: None,
+            retime_mode: RetimeMode::default(),
             blend_mode: BlendMode::default(),
+            scale_mode: ScaleMode::default(),
             effects: Vec::new(),
+            markers: Vec::new(),
+        }
+    }
+
+    /// Create a clip showing a still image for `duration`. It holds the image like a
+    /// freeze frame, so it can be extended without running out of source media.
+    pub fn new_still(
+        name: String,
+        source_media: MediaId,
+        timeline_position: Timecode,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            time_remap: Some(TimeRemap::frozen(duration)),
+            scale_mode: ScaleMode::Fit,
+            ..Self::new(
+                name,
+                source_media,
+                timeline_position,
+                Timecode::from_seconds(0.0),
+                Timecode::from_seconds(0.0),
+            )
         }
     }
 
-    /// Duration of this clip on the timeline (considering speed)
+    /// Create a generator clip lasting `duration` on the timeline
+    pub fn new_generator(
+        name: String,
+        generator: Generator,
+        timeline_position: Timecode,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            id: ClipId::new(),
+            name,
+            source: ClipSource::Generator(generator),
+            timeline_position,
+            source_in: Timecode::from_seconds(0.0),
+            source_out: Timecode(duration),
+            speed: 1.0,
+            time_remap: None,
+            retime_mode: RetimeMode::default(),
+            blend_mode: BlendMode::default(),
+            scale_mode: ScaleMode::default(),
+            effects: Vec::new(),
+            markers: Vec::new(),
+        }
+    }
+
+    /// Create a compound clip showing the first `duration` of a nested sequence
+    pub fn new_compound(
+        name: String,
+        sequence_id: SequenceId,
+        timeline_position: Timecode,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            id: ClipId::new(),
+            name,
+            source: ClipSource::Sequence(sequence_id),
+            timeline_position,
+            source_in: Timecode::from_seconds(0.0),
+            source_out: Timecode(duration),
+            speed: 1.0,
+            time_remap: None,
+            retime_mode: RetimeMode::default(),
+            blend_mode: BlendMode::default(),
+            scale_mode: ScaleMode::default(),
+            effects: Vec::new(),
+            markers: Vec::new(),
+        }
+    }
+
+    /// Source media ID, if this clip references a media item
+    pub fn media_id(&self) -> Option<&MediaId> {
+        match &self.source {
+            ClipSource::Media(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Generator, if this is a generator clip
+    pub fn generator(&self) -> Option<&Generator> {
+        match &self.source {
+            ClipSource::Generator(generator) => Some(generator),
+            _ => None,
+        }
+    }
+
+    /// Nested sequence ID, if this is a compound clip
+    pub fn nested_sequence(&self) -> Option<&SequenceId> {
+        match &self.source {
+            ClipSource::Sequence(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Constant playback speed, ignored while a time remap is set
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Set a constant playback speed. Negative speeds play in reverse.
+    pub fn set_speed(&mut self, speed: f64) -> Result<()> {
+        if speed == 0.0 || !speed.is_finite() {
+            return Err(VxError::InvalidParameter(format!(
+                "Invalid clip speed {speed}, use a freeze frame to hold a frame"
+            )));
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// Whether this clip plays its source backwards at a constant speed
+    pub fn is_reversed(&self) -> bool {
+        self.time_remap.is_none() && self.speed < 0.0
+    }
+
+    /// Toggle reverse playback, keeping the speed magnitude
+    pub fn reverse(&mut self) {
+        self.speed = -self.speed;
+    }
+
+    /// Duration of this clip on the timeline (considering speed).
+    /// An invalid speed gives zero duration.
     pub fn timeline_duration(&self) -> Duration {
+        if let Some(remap) = &self.time_remap {
+            return remap.duration;
+        }
         let source_duration = self.source_out.as_duration() - self.source_in.as_duration();
-        Duration::from_secs_f64(source_duration.as_secs_f64() / self.speed)
+        Duration::try_from_secs_f64(source_duration.as_secs_f64() / self.speed.abs())
+            .unwrap_or_default()
     }
 
     /// End position of this clip on the timeline
@@ -83,19 +251,44 @@ impl Clip {
         Timecode(self.timeline_position.as_duration() + self.timeline_duration())
     }
 
+    /// Range occupied by this clip on the timeline
+    pub fn timeline_range(&self) -> TimeRange {
+        TimeRange::new(self.timeline_position, self.timeline_duration())
+    }
+
     /// Check if this clip contains given timeline position
     pub fn contains_time(&self, time: Timecode) -> bool {
-        time >= self.timeline_position && time < self.timeline_end()
+        self.timeline_range().contains(time)
     }
 
-    /// Check if this clip overlaps with given time range
+    /// Check if this clip overlaps with given time range (touching does not count)
     pub fn overlaps_with(&self, range: TimeRange) -> bool {
-        let clip_range = TimeRange::new(self.timeline_position, self.timeline_duration());
-        // Overlap if either start or end is within the range
-        clip_range.contains(range.start)
-            || clip_range.contains(range.end())
-            || range.contains(clip_range.start)
-            || range.contains(clip_range.end())
+        self.timeline_range().intersects(range)
+    }
+
+    /// Markers mapped to timeline time, skipping those outside the clip
+    pub fn timeline_markers(&self) -> impl Iterator<Item = (Timecode, &Marker)> {
+        let duration = self.timeline_duration();
+        self.markers
+            .iter()
+            .filter(move |m| m.time.as_duration() < duration)
+            .map(|m| {
+                (
+                    Timecode(self.timeline_position.as_duration() + m.time.as_duration()),
+                    m,
+                )
+            })
+    }
+
+    /// Map timeline time to time relative to the clip start (used for keyframe evaluation)
+    pub fn clip_time(&self, timeline_time: Timecode) -> Option<Timecode> {
+        if !self.contains_time(timeline_time) {
+            return None;
+        }
+
+        Some(Timecode(
+            timeline_time.as_duration() - self.timeline_position.as_duration(),
+        ))
     }
 
     /// Map timeline time to source media time
@@ -105,11 +298,155 @@ impl Clip {
         }
 
         let offset_in_clip = timeline_time.as_duration() - self.timeline_position.as_duration();
-        let source_offset = Duration::from_secs_f64(offset_in_clip.as_secs_f64() * self.speed);
-        let source_time = self.source_in.as_duration() + source_offset;
+        if let Some(remap) = &self.time_remap {
+            // Reversing past the start holds the first source frame
+            let offset = remap.source_offset(Timecode(offset_in_clip));
+            let source_time = (self.source_in.as_seconds() + offset).max(0.0);
+            return Some(Timecode::from_seconds(source_time));
+        }
+
+        let source_offset =
+            Duration::from_secs_f64(offset_in_clip.as_secs_f64() * self.speed.abs());
+        let source_time = if self.is_reversed() {
+            self.source_out.as_duration().saturating_sub(source_offset)
+        } else {
+            self.source_in.as_duration() + source_offset
+        };
 
         Some(Timecode(source_time))
     }
+
+    /// Source media played during a timeline range, clipped to this clip.
+    /// Used to pull audio in sync with the frames shown.
+    pub fn source_span(&self, range: TimeRange) -> Option<SourceSpan> {
+        let range = self.timeline_range().intersection(range)?;
+        let start = self.timeline_to_source_time(range.start)?;
+        let end = self.source_time_at_end(range.end());
+        Some(if end < start {
+            SourceSpan {
+                range: TimeRange::from_bounds(end, start),
+                reversed: true,
+            }
+        } else {
+            SourceSpan {
+                range: TimeRange::from_bounds(start, end),
+                reversed: false,
+            }
+        })
+    }
+
+    /// Source time reached at a timeline time, including the clip end itself
+    fn source_time_at_end(&self, timeline_time: Timecode) -> Timecode {
+        if let Some(time) = self.timeline_to_source_time(timeline_time) {
+            return time;
+        }
+        match &self.time_remap {
+            Some(remap) => {
+                let offset = remap.source_offset(Timecode(remap.duration));
+                Timecode::from_seconds((self.source_in.as_seconds() + offset).max(0.0))
+            }
+            None if self.is_reversed() => self.source_in,
+            None => self.source_out,
+        }
+    }
+
+    /// Playback speed at a timeline time
+    pub fn speed_at(&self, timeline_time: Timecode) -> Option<f64> {
+        let clip_time = self.clip_time(timeline_time)?;
+        Some(
+            self.time_remap
+                .as_ref()
+                .map_or(self.speed, |remap| remap.speed_at(clip_time)),
+        )
+    }
+
+    /// Whether this clip holds a single source frame
+    pub fn is_freeze_frame(&self) -> bool {
+        self.time_remap.as_ref().is_some_and(TimeRemap::is_frozen)
+    }
+
+    /// New clip holding the frame shown at `timeline_time` for `duration`,
+    /// placed at that time. Returns None if the time is outside this clip.
+    pub fn freeze_frame(&self, timeline_time: Timecode, duration: Duration) -> Option<Clip> {
+        let source_time = self.timeline_to_source_time(timeline_time)?;
+        Some(Clip {
+            id: ClipId::new(),
+            name: format!("{} (Freeze Frame)", self.name),
+            timeline_position: timeline_time,
+            source_in: source_time,
+            source_out: source_time,
+            speed: 1.0,
+            time_remap: Some(TimeRemap::frozen(duration)),
+            retime_mode: RetimeMode::FrameSampling,
+            markers: Vec::new(),
+            ..self.clone()
+        })
+    }
+
+    /// Change how long a still or freeze frame clip lasts. Other clips are
+    /// limited by their source range and are trimmed instead. The duration must
+    /// not be zero.
+    pub fn set_hold_duration(&mut self, duration: Duration) -> Result<()> {
+        if duration.is_zero() {
+            return Err(VxError::InvalidParameter(format!(
+                "Clip '{}' cannot hold for zero time",
+                self.name
+            )));
+        }
+        match &mut self.time_remap {
+            Some(remap) if remap.is_frozen() => {
+                remap.duration = duration;
+                Ok(())
+            }
+            _ => Err(VxError::InvalidParameter(format!(
+                "Clip '{}' does not hold a single frame",
+                self.name
+            ))),
+        }
+    }
+
+    /// Split this clip at a timeline time. This clip keeps the left part and the
+    /// right part is returned as a new clip. Returns None if `time` is not strictly
+    /// inside the clip.
+    pub fn split_at(&mut self, time: Timecode) -> Option<Clip> {
+        if time <= self.timeline_position {
+            return None;
+        }
+        let source_time = self.timeline_to_source_time(time)?;
+        let offset = time.as_duration() - self.timeline_position.as_duration();
+
+        let mut right = self.clone();
+        right.id = ClipId::new();
+        right.timeline_position = time;
+        right.time_remap = self
+            .time_remap
+            .as_mut()
+            .map(|remap| remap.split_at(Timecode(offset)));
+        right.markers = self
+            .markers
+            .iter()
+            .filter(|m| m.time.as_duration() >= offset)
+            .cloned()
+            .map(|mut m| {
+                m.time = Timecode(m.time.as_duration() - offset);
+                m
+            })
+            .collect();
+
+        // A reversed clip plays later source first, so the left part keeps the end
+        if self.is_reversed() {
+            right.source_out = source_time;
+            self.source_in = source_time;
+        } else {
+            right.source_in = source_time;
+            if self.time_remap.is_none() {
+                self.source_out = source_time;
+            }
+        }
+        self.markers.retain(|m| m.time.as_duration() < offset);
+
+        Some(right)
+    }
 }
 
 #[cfg(test)]
@@ -140,12 +477,67 @@ mod tests {
             Timecode::from_seconds(10.0),
         );
 
-        clip.speed = 2.0; // 2x speed
+        clip.set_speed(2.0).unwrap(); // 2x speed
 
         // Source is 10 seconds, but at 2x speed = 5 seconds on timeline
         assert_eq!(clip.timeline_duration().as_secs(), 5);
     }
 
+    #[test]
+    fn test_generator_clip() {
+        let clip = Clip::new_generator(
+            "title".to_string(),
+            Generator::SolidColor(Default::default()),
+            Timecode::from_seconds(5.0),
+            Duration::from_secs(3),
+        );
+
+        assert!(clip.media_id().is_none());
+        assert_eq!(clip.timeline_end(), Timecode::from_seconds(8.0));
+    }
+
+    #[test]
+    fn test_clip_split() {
+        let mut clip = Clip::new(
+            "test".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(10.0),
+            Timecode::from_seconds(20.0),
+            Timecode::from_seconds(30.0),
+        );
+
+        let right = clip.split_at(Timecode::from_seconds(14.0)).unwrap();
+        assert_eq!(clip.timeline_end(), Timecode::from_seconds(14.0));
+        assert_eq!(clip.source_out, Timecode::from_seconds(24.0));
+        assert_eq!(right.timeline_position, Timecode::from_seconds(14.0));
+        assert_eq!(right.source_in, Timecode::from_seconds(24.0));
+        assert_ne!(clip.id, right.id);
+
+        assert!(clip.split_at(Timecode::from_seconds(10.0)).is_none());
+        assert!(clip.split_at(Timecode::from_seconds(14.0)).is_none());
+    }
+
+    #[test]
+    fn test_clip_overlaps_with() {
+        let clip = Clip::new(
+            "test".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(10.0),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(5.0),
+        );
+
+        let range = |start: f64, end: f64| {
+            TimeRange::from_bounds(Timecode::from_seconds(start), Timecode::from_seconds(end))
+        };
+        assert!(clip.overlaps_with(range(12.0, 13.0)));
+        assert!(clip.overlaps_with(range(0.0, 20.0)));
+        assert!(clip.overlaps_with(range(14.0, 16.0)));
+        assert!(!clip.overlaps_with(range(5.0, 10.0)));
+        assert!(!clip.overlaps_with(range(15.0, 20.0)));
+        assert!(!clip.overlaps_with(range(12.0, 12.0)));
+    }
+
     #[test]
     fn test_clip_contains_time() {
         let clip = Clip::new(
@@ -169,10 +561,164 @@ mod tests {
             Timecode::from_seconds(20.0), // Source in
             Timecode::from_seconds(30.0), // Source out
         );
-        clip.speed = 2.0; // 2x speed
+        clip.set_speed(2.0).unwrap(); // 2x speed
 
         // Timeline 12 seconds -> 2 seconds into clip -> 4 seconds in source (2x) -> source 24 seconds
         let source_time = clip.timeline_to_source_time(Timecode::from_seconds(12.0));
         assert_eq!(source_time.unwrap().as_seconds(), 24.0);
     }
+
+    #[test]
+    fn test_time_remap_ramp() {
+        let mut clip = Clip::new(
+            "test".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(10.0),
+            Timecode::from_seconds(20.0),
+            Timecode::from_seconds(30.0),
+        );
+        // Normal speed for 2s, linear ramp down to a freeze at 4s
+        let mut remap = TimeRemap::new(Duration::from_secs(6));
+        remap.set_speed(
+            Timecode::from_seconds(2.0),
+            1.0,
+            crate::animation::Interpolation::Linear,
+        );
+        remap.set_speed(
+            Timecode::from_seconds(4.0),
+            0.0,
+            crate::animation::Interpolation::Linear,
+        );
+        clip.time_remap = Some(remap);
+
+        assert_eq!(clip.timeline_end(), Timecode::from_seconds(16.0));
+        let source = |t: f64| {
+            clip.timeline_to_source_time(Timecode::from_seconds(t))
+                .unwrap()
+                .as_seconds()
+        };
+        assert!((source(12.0) - 22.0).abs() < 1e-6);
+        assert!((source(14.0) - 23.0).abs() < 1e-6);
+        assert!((source(15.5) - 23.0).abs() < 1e-6);
+        assert_eq!(clip.speed_at(Timecode::from_seconds(13.0)), Some(0.5));
+
+        // Splitting keeps the source mapping continuous
+        let before = source(13.0);
+        let right = clip.split_at(Timecode::from_seconds(13.0)).unwrap();
+        assert_eq!(clip.timeline_end(), Timecode::from_seconds(13.0));
+        assert_eq!(right.timeline_end(), Timecode::from_seconds(16.0));
+        let after = right
+            .timeline_to_source_time(Timecode::from_seconds(15.5))
+            .unwrap()
+            .as_seconds();
+        assert!((right.source_in.as_seconds() - before).abs() < 1e-6);
+        assert!((after - 23.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_reverse_clip() {
+        let mut clip = Clip::new(
+            "test".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(10.0),
+            Timecode::from_seconds(20.0),
+            Timecode::from_seconds(30.0),
+        );
+        assert!(clip.set_speed(0.0).is_err());
+        assert!(clip.set_speed(f64::NAN).is_err());
+        clip.set_speed(-2.0).unwrap();
+
+        assert_eq!(clip.timeline_duration(), Duration::from_secs(5));
+        let source = clip.timeline_to_source_time(Timecode::from_seconds(12.0));
+        assert_eq!(source.unwrap().as_seconds(), 26.0);
+
+        let span = clip
+            .source_span(TimeRange::from_bounds(
+                Timecode::from_seconds(12.0),
+                Timecode::from_seconds(20.0),
+            ))
+            .unwrap();
+        assert!(span.reversed);
+        assert_eq!(span.range.start.as_seconds(), 20.0);
+        assert_eq!(span.range.end().as_seconds(), 26.0);
+
+        let right = clip.split_at(Timecode::from_seconds(12.0)).unwrap();
+        assert_eq!(clip.source_in.as_seconds(), 26.0);
+        assert_eq!(clip.source_out.as_seconds(), 30.0);
+        assert_eq!(right.source_in.as_seconds(), 20.0);
+        assert_eq!(right.source_out.as_seconds(), 26.0);
+        assert_eq!(right.timeline_end(), Timecode::from_seconds(15.0));
+    }
+
+    #[test]
+    fn test_zero_speed_does_not_panic() {
+        let mut clip = Clip::new(
+            "test".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(10.0),
+        );
+        // set_speed refuses zero, but a hand-edited project file can still hold it
+        clip.speed = 0.0;
+        assert_eq!(clip.timeline_duration(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_still_clip_extends_freely() {
+        use crate::timeline::{Track, TrackId, TrackType};
+
+        let still = Clip::new_still(
+            "photo".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(0.0),
+            Duration::from_secs(5),
+        );
+        let next = Clip::new_still(
+            "next".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(60.0),
+            Duration::from_secs(5),
+        );
+        let still_id = still.id.clone();
+        assert_eq!(still.scale_mode, ScaleMode::Fit);
+
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(still);
+        track.add_clip(next);
+        track
+            .set_hold_duration(&still_id, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(
+            track.get_clip(&still_id).unwrap().timeline_end(),
+            Timecode::from_seconds(60.0)
+        );
+        assert_eq!(
+            track.clip_at_time(Timecode::from_seconds(59.0)).unwrap().id,
+            still_id
+        );
+        assert!(
+            track
+                .set_hold_duration(&still_id, Duration::from_secs(61))
+                .is_err()
+        );
+        assert!(matches!(
+            track.set_hold_duration(&still_id, Duration::ZERO),
+            Err(VxError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            track.get_clip(&still_id).unwrap().timeline_end(),
+            Timecode::from_seconds(60.0)
+        );
+
+        // Ordinary clips are limited by their source range
+        let mut clip = Clip::new(
+            "video".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(10.0),
+        );
+        assert!(clip.set_hold_duration(Duration::from_secs(20)).is_err());
+    }
 }
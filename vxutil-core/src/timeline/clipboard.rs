@@ -0,0 +1,32 @@
+//! Clipboard for copying and pasting ranges of a sequence
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{Clip, TrackType};
+
+/// Clips copied from one track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardTrack {
+    pub track_type: TrackType,
+
+    /// Index of the source track among the tracks of its type
+    pub track_index: usize,
+
+    /// Copied clips, positioned relative to the start of the copied range
+    pub clips: Vec<Clip>,
+}
+
+/// Clips copied from a range of a sequence, across tracks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceClipboard {
+    /// Length of the copied range
+    pub duration: Duration,
+    pub tracks: Vec<ClipboardTrack>,
+}
+
+impl SequenceClipboard {
+    pub fn is_empty(&self) -> bool {
+        self.tracks.iter().all(|t| t.clips.is_empty())
+    }
+}
@@ -0,0 +1,133 @@
+//! Interval index for clip lookup on a track
+
+use std::ops::Range;
+
+use super::Clip;
+use crate::types::{TimeRange, Timecode};
+
+/// Lookup index over clips sorted by timeline position.
+///
+/// Stores the running maximum of clip end times, so both ends of a query can be
+/// found by binary search even when clips overlap. On tracks without overlaps the
+/// candidate span contains exactly the matching clips.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ClipIndex {
+    max_ends: Vec<Timecode>,
+}
+
+impl ClipIndex {
+    /// Build the index. `clips` must be sorted by timeline position.
+    pub fn build(clips: &[Clip]) -> Self {
+        let mut max_ends = Vec::with_capacity(clips.len());
+        let mut max_end = Timecode::from_seconds(0.0);
+        for clip in clips {
+            max_end = max_end.max(clip.timeline_end());
+            max_ends.push(max_end);
+        }
+        Self { max_ends }
+    }
+
+    /// Latest clip end time
+    pub fn end(&self) -> Option<Timecode> {
+        self.max_ends.last().copied()
+    }
+
+    /// Indices of clips that may contain `time`
+    pub fn candidates_at(&self, clips: &[Clip], time: Timecode) -> Range<usize> {
+        let last = clips.partition_point(|c| c.timeline_position <= time);
+        let first = self.max_ends[..last].partition_point(|end| *end <= time);
+        first..last
+    }
+
+    /// Indices of clips that may overlap `range`
+    pub fn candidates_in(&self, clips: &[Clip], range: TimeRange) -> Range<usize> {
+        let last = clips.partition_point(|c| c.timeline_position < range.end());
+        let first = self.max_ends[..last].partition_point(|end| *end <= range.start);
+        first..last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::media::MediaId;
+    use crate::timeline::{Clip, Track, TrackId, TrackType};
+    use crate::types::{TimeRange, Timecode};
+    use proptest::prelude::*;
+    use std::time::Duration;
+
+    fn ms(value: u64) -> Timecode {
+        Timecode(Duration::from_millis(value))
+    }
+
+    fn track(clips: &[(u64, u64)]) -> Track {
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        for &(start, len) in clips {
+            track.add_clip(Clip::new(
+                "clip".to_string(),
+                MediaId::new(),
+                ms(start),
+                ms(0),
+                ms(len),
+            ));
+        }
+        track
+    }
+
+    proptest! {
+        // Overlapping and zero-length clips included on purpose
+        #[test]
+        fn prop_lookup_matches_linear_scan(
+            clips in prop::collection::vec((0u64..1000, 0u64..100), 0..40),
+            start in 0u64..1200,
+            len in 0u64..200,
+        ) {
+            let track = track(&clips);
+            let range = TimeRange::new(ms(start), Duration::from_millis(len));
+
+            let expected: Vec<_> = track.clips().iter().filter(|c| c.overlaps_with(range)).collect();
+            let found = track.clips_in_range(range);
+            prop_assert_eq!(
+                found.iter().map(|c| &c.id).collect::<Vec<_>>(),
+                expected.iter().map(|c| &c.id).collect::<Vec<_>>()
+            );
+
+            let expected = track.clips().iter().find(|c| c.contains_time(ms(start)));
+            prop_assert_eq!(
+                track.clip_at_time(ms(start)).map(|c| &c.id),
+                expected.map(|c| &c.id)
+            );
+
+            let end = track.clips().iter().map(|c| c.timeline_end()).max();
+            prop_assert_eq!(track.end(), end);
+        }
+    }
+
+    #[test]
+    fn test_index_follows_edits() {
+        let mut track = track(&[(0, 1000), (1000, 1000)]);
+        assert!(track.clip_at_time(ms(2500)).is_none());
+
+        track.add_clip(Clip::new(
+            "late".to_string(),
+            MediaId::new(),
+            ms(2000),
+            ms(0),
+            ms(1000),
+        ));
+        assert_eq!(track.clip_at_time(ms(2500)).unwrap().name, "late");
+
+        let first = track.clips()[0].id.clone();
+        track.remove_clip(&first);
+        assert!(track.clip_at_time(ms(500)).is_none());
+
+        track.shift_clips_after(ms(1000), Duration::from_millis(1000));
+        assert_eq!(track.clip_at_time(ms(1500)).unwrap().name, "late");
+        assert_eq!(track.end(), Some(ms(2000)));
+
+        let late = track.clip_at_time(ms(1500)).unwrap().id.clone();
+        track.update_clip(&late, |clip| clip.timeline_position = ms(5000));
+        assert!(track.clip_at_time(ms(1500)).is_none());
+        assert_eq!(track.clip_at_time(ms(5500)).unwrap().name, "late");
+        assert_eq!(track.end(), Some(ms(6000)));
+    }
+}
@@ -0,0 +1,88 @@
+//! Markers and chapter points
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::types::{LabelColor, TimeRange, Timecode};
+
+/// Unique identifier for a marker
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MarkerId(Uuid);
+
+impl MarkerId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for MarkerId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Kind of marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MarkerKind {
+    #[default]
+    Standard,
+    /// Written into exported files as a chapter point
+    Chapter,
+}
+
+/// A named point (or range) of interest.
+///
+/// Sequence markers are placed in timeline time, clip markers in time relative
+/// to the clip start so they move with the clip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Marker {
+    pub id: MarkerId,
+    pub name: String,
+    pub color: LabelColor,
+    pub note: String,
+    pub time: Timecode,
+
+    /// Length of the marked range (None for a single point)
+    pub duration: Option<Duration>,
+
+    pub kind: MarkerKind,
+}
+
+impl Marker {
+    pub fn new(name: String, time: Timecode) -> Self {
+        Self {
+            id: MarkerId::new(),
+            name,
+            color: LabelColor::default(),
+            note: String::new(),
+            time,
+            duration: None,
+            kind: MarkerKind::Standard,
+        }
+    }
+
+    pub fn chapter(name: String, time: Timecode) -> Self {
+        Self {
+            kind: MarkerKind::Chapter,
+            ..Self::new(name, time)
+        }
+    }
+
+    pub fn is_chapter(&self) -> bool {
+        self.kind == MarkerKind::Chapter
+    }
+
+    /// Marked range, if this marker has a duration
+    pub fn range(&self) -> Option<TimeRange> {
+        self.duration.map(|d| TimeRange::new(self.time, d))
+    }
+}
+
+/// A chapter as written into an exported file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start: Timecode,
+    pub end: Timecode,
+}
@@ -2,10 +2,28 @@
 
 mod blend_mode;
 mod clip;
+mod clipboard;
+mod index;
+mod marker;
+mod scale;
 mod sequence;
+mod snapping;
+mod targets;
+mod time_remap;
 mod track;
+mod transition;
 
 pub use blend_mode::BlendMode;
-pub use clip::{Clip, ClipId};
-pub use sequence::Sequence;
+pub use clip::{Clip, ClipId, ClipSource, SourceSpan};
+pub use clipboard::{ClipboardTrack, SequenceClipboard};
+pub use marker::{Chapter, Marker, MarkerId, MarkerKind};
+pub use scale::{Placement, ScaleMode};
+pub use sequence::{Sequence, SequenceId};
+pub use snapping::{SnapTarget, SnapTargetKind, Snapper};
+pub use targets::{EditMode, TrackTargets};
+pub use time_remap::{RetimeMode, TimeRemap};
 pub use track::{Track, TrackId, TrackType};
+pub use transition::{
+    CrossfadeCurve, Transition, TransitionAlignment, TransitionDirection, TransitionId,
+    TransitionKind,
+};
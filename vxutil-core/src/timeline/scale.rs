@@ -0,0 +1,79 @@
+//! Scaling of clip frames to the sequence resolution
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Resolution;
+
+/// How a clip frame whose size differs from the sequence resolution is scaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScaleMode {
+    /// Scale to fit entirely inside the frame, keeping the aspect ratio (letterboxed)
+    Fit,
+    /// Scale to cover the whole frame, keeping the aspect ratio (edges cropped)
+    Fill,
+    /// Scale to the frame size, ignoring the aspect ratio
+    #[default]
+    Stretch,
+    /// Keep the original pixel size, centered
+    None,
+}
+
+/// Where a scaled frame lands on the canvas. The offset is negative when the
+/// frame is larger than the canvas and gets cropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub x: i64,
+    pub y: i64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScaleMode {
+    /// Placement of a `source` sized frame on a `canvas` sized frame
+    pub fn placement(&self, source: Resolution, canvas: Resolution) -> Placement {
+        let (width, height) = match self {
+            ScaleMode::Stretch => (canvas.width, canvas.height),
+            ScaleMode::None => (source.width, source.height),
+            ScaleMode::Fit | ScaleMode::Fill if source.width == 0 || source.height == 0 => (0, 0),
+            ScaleMode::Fit | ScaleMode::Fill => {
+                let x_scale = canvas.width as f64 / source.width as f64;
+                let y_scale = canvas.height as f64 / source.height as f64;
+                let scale = if *self == ScaleMode::Fit {
+                    x_scale.min(y_scale)
+                } else {
+                    x_scale.max(y_scale)
+                };
+                (
+                    (source.width as f64 * scale).round() as u32,
+                    (source.height as f64 * scale).round() as u32,
+                )
+            }
+        };
+        Placement {
+            x: (i64::from(canvas.width) - i64::from(width)) / 2,
+            y: (i64::from(canvas.height) - i64::from(height)) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placement() {
+        let canvas = Resolution::FULL_HD;
+        let portrait = Resolution::new(1080, 1920);
+        let place = |mode: ScaleMode| {
+            let p = mode.placement(portrait, canvas);
+            (p.x, p.y, p.width, p.height)
+        };
+
+        assert_eq!(place(ScaleMode::Fit), (656, 0, 608, 1080));
+        assert_eq!(place(ScaleMode::Fill), (0, -1166, 1920, 3413));
+        assert_eq!(place(ScaleMode::Stretch), (0, 0, 1920, 1080));
+        assert_eq!(place(ScaleMode::None), (420, -420, 1080, 1920));
+    }
+}
@@ -1,13 +1,39 @@
 //! Sequence - a timeline containing multiple tracks
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
 
-use super::{Clip, Track, TrackId, TrackType};
-use crate::types::{FrameRate, Resolution, Timecode};
+use super::track::find_gaps;
+use super::{
+    Chapter, Clip, ClipId, ClipboardTrack, EditMode, Marker, MarkerId, SequenceClipboard, Track,
+    TrackId, TrackTargets, TrackType,
+};
+use crate::types::{FrameRate, Resolution, TimeRange, Timecode};
+use crate::{Result, VxError};
+
+/// Unique identifier for a sequence
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SequenceId(Uuid);
+
+impl SequenceId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for SequenceId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// A sequence is a timeline containing multiple tracks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sequence {
+    #[serde(default)]
+    pub id: SequenceId,
     pub name: String,
     pub frame_rate: FrameRate,
     pub resolution: Resolution,
@@ -20,26 +46,179 @@ pub struct Sequence {
 
     /// Current playhead position
     pub playhead: Timecode,
+
+    /// Sequence markers, sorted by time
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+
+    /// In point for range operations
+    #[serde(default)]
+    pub in_point: Option<Timecode>,
+
+    /// Out point for range operations
+    #[serde(default)]
+    pub out_point: Option<Timecode>,
+
+    /// Tracks receiving clips in insert and overwrite edits
+    #[serde(default)]
+    pub targets: TrackTargets,
+
+    /// Lowest track ID that has never been handed out
+    #[serde(default)]
+    next_track_id: usize,
 }
 
 impl Sequence {
     pub fn new(name: String, frame_rate: FrameRate, resolution: Resolution) -> Self {
         Self {
+            id: SequenceId::new(),
             name,
             frame_rate,
             resolution,
             video_tracks: Vec::new(),
             audio_tracks: Vec::new(),
             playhead: Timecode::from_seconds(0.0),
+            markers: Vec::new(),
+            in_point: None,
+            out_point: None,
+            targets: TrackTargets::default(),
+            next_track_id: 0,
+        }
+    }
+
+    /// Add a track on top of the others of its type. The track keeps its ID if no
+    /// other track uses it, otherwise it gets a newly allocated one.
+    pub fn add_track(&mut self, mut track: Track) -> TrackId {
+        if self.get_track(track.id()).is_some() {
+            track.set_id(self.allocate_track_id());
+        } else {
+            self.next_track_id = self.next_track_id.max(track.id().0 + 1);
+        }
+        let id = track.id();
+        self.tracks_mut(track.track_type).push(track);
+        id
+    }
+
+    /// Create an empty track on top of the others of its type
+    pub fn new_track(&mut self, track_type: TrackType, name: String) -> TrackId {
+        let id = self.allocate_track_id();
+        self.tracks_mut(track_type)
+            .push(Track::new(id, name, track_type));
+        id
+    }
+
+    /// Create an empty track at `index` among the tracks of its type
+    pub fn insert_track(
+        &mut self,
+        track_type: TrackType,
+        index: usize,
+        name: String,
+    ) -> Result<TrackId> {
+        if index > self.tracks(track_type).len() {
+            return Err(VxError::InvalidParameter(format!(
+                "Track index {index} is out of range"
+            )));
         }
+        let id = self.allocate_track_id();
+        self.tracks_mut(track_type)
+            .insert(index, Track::new(id, name, track_type));
+        Ok(id)
     }
 
-    /// Add a new track
-    pub fn add_track(&mut self, track: Track) {
-        match track.track_type {
-            TrackType::Video => self.video_tracks.push(track),
-            TrackType::Audio => self.audio_tracks.push(track),
+    /// Delete a track together with its clips
+    pub fn remove_track(&mut self, track_id: TrackId) -> Result<Track> {
+        let (track_type, index) = self.track_position(track_id)?;
+        let tracks = self.tracks_mut(track_type);
+        if tracks[index].locked {
+            return Err(VxError::Timeline(format!(
+                "Track '{}' is locked",
+                tracks[index].name
+            )));
         }
+        let track = tracks.remove(index);
+        self.targets.clear(track_id);
+        Ok(track)
+    }
+
+    /// Move a track to `index` among the tracks of its type. For video tracks a
+    /// higher index is composited on top.
+    pub fn move_track(&mut self, track_id: TrackId, index: usize) -> Result<()> {
+        let (track_type, from) = self.track_position(track_id)?;
+        let tracks = self.tracks_mut(track_type);
+        if index >= tracks.len() {
+            return Err(VxError::InvalidParameter(format!(
+                "Track index {index} is out of range"
+            )));
+        }
+        let track = tracks.remove(from);
+        tracks.insert(index, track);
+        Ok(())
+    }
+
+    /// Move a track one place up (towards the top of the z-order). No-op at the top.
+    pub fn raise_track(&mut self, track_id: TrackId) -> Result<()> {
+        let (track_type, index) = self.track_position(track_id)?;
+        if index + 1 < self.tracks(track_type).len() {
+            self.move_track(track_id, index + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Move a track one place down. No-op at the bottom.
+    pub fn lower_track(&mut self, track_id: TrackId) -> Result<()> {
+        let (_, index) = self.track_position(track_id)?;
+        if index > 0 {
+            self.move_track(track_id, index - 1)?;
+        }
+        Ok(())
+    }
+
+    /// Target a track for insert and overwrite edits of its type
+    pub fn target_track(&mut self, track_id: TrackId) -> Result<()> {
+        let (track_type, _) = self.track_position(track_id)?;
+        self.targets.set(track_type, Some(track_id));
+        Ok(())
+    }
+
+    fn allocate_track_id(&mut self) -> TrackId {
+        let used = self
+            .video_tracks
+            .iter()
+            .chain(self.audio_tracks.iter())
+            .map(|t| t.id().0 + 1)
+            .max()
+            .unwrap_or(0);
+        let id = self.next_track_id.max(used);
+        self.next_track_id = id + 1;
+        TrackId(id)
+    }
+
+    fn tracks(&self, track_type: TrackType) -> &Vec<Track> {
+        match track_type {
+            TrackType::Video => &self.video_tracks,
+            TrackType::Audio => &self.audio_tracks,
+        }
+    }
+
+    fn tracks_mut(&mut self, track_type: TrackType) -> &mut Vec<Track> {
+        match track_type {
+            TrackType::Video => &mut self.video_tracks,
+            TrackType::Audio => &mut self.audio_tracks,
+        }
+    }
+
+    /// Type and index of a track
+    fn track_position(&self, track_id: TrackId) -> Result<(TrackType, usize)> {
+        [TrackType::Video, TrackType::Audio]
+            .into_iter()
+            .find_map(|track_type| {
+                let index = self
+                    .tracks(track_type)
+                    .iter()
+                    .position(|t| t.id() == track_id)?;
+                Some((track_type, index))
+            })
+            .ok_or_else(|| VxError::NotFound("Track".to_string()))
     }
 
     /// Get track by ID
@@ -47,7 +226,7 @@ impl Sequence {
         self.video_tracks
             .iter()
             .chain(self.audio_tracks.iter())
-            .find(|t| t.id == track_id)
+            .find(|t| t.id() == track_id)
     }
 
     /// Get mutable track by ID
@@ -55,45 +234,1213 @@ impl Sequence {
         self.video_tracks
             .iter_mut()
             .chain(self.audio_tracks.iter_mut())
-            .find(|t| t.id == track_id)
+            .find(|t| t.id() == track_id)
     }
 
     /// Calculate total duration of the sequence (longest clip end time)
     pub fn duration(&self) -> Timecode {
-        let max_video = self
-            .video_tracks
-            .iter()
-            .flat_map(|t| t.clips.iter())
-            .map(|c| c.timeline_end())
-            .max();
-
-        let max_audio = self
-            .audio_tracks
+        self.video_tracks
             .iter()
-            .flat_map(|t| t.clips.iter())
-            .map(|c| c.timeline_end())
-            .max();
-
-        max_video
-            .into_iter()
-            .chain(max_audio)
+            .chain(self.audio_tracks.iter())
+            .filter_map(|t| t.end())
             .max()
             .unwrap_or(Timecode::from_seconds(0.0))
     }
 
     /// Get all video clips at given time (from all tracks, sorted by z-order)
     pub fn video_clips_at_time(&self, time: Timecode) -> Vec<&Clip> {
-        self.video_tracks
-            .iter()
+        self.visible_video_tracks()
             .filter_map(|track| track.clip_at_time(time))
             .collect()
     }
 
     /// Get all audio clips at given time
     pub fn audio_clips_at_time(&self, time: Timecode) -> Vec<&Clip> {
+        self.audible_tracks()
+            .filter_map(|track| track.clip_at_time(time))
+            .collect()
+    }
+
+    /// Video tracks that are composited, lowest first
+    pub fn visible_video_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.video_tracks.iter().filter(|t| t.visible)
+    }
+
+    /// Audio tracks that are heard. Muted tracks never are; while any track is
+    /// soloed, only soloed tracks are.
+    pub fn audible_tracks(&self) -> impl Iterator<Item = &Track> {
+        let any_solo = self.audio_tracks.iter().any(|t| t.solo);
         self.audio_tracks
             .iter()
-            .filter_map(|track| track.clip_at_time(time))
+            .filter(move |t| !t.muted && (t.solo || !any_solo))
+    }
+
+    /// Solo or unsolo an audio track. An exclusive solo unsolos every other track.
+    pub fn set_solo(&mut self, track_id: TrackId, solo: bool, exclusive: bool) -> Result<()> {
+        let (track_type, index) = self.track_position(track_id)?;
+        if track_type != TrackType::Audio {
+            return Err(VxError::InvalidParameter(
+                "Only audio tracks can be soloed".to_string(),
+            ));
+        }
+        if exclusive {
+            for track in &mut self.audio_tracks {
+                track.solo = false;
+            }
+        }
+        self.audio_tracks[index].solo = solo;
+        Ok(())
+    }
+
+    /// Add a sequence marker
+    pub fn add_marker(&mut self, marker: Marker) -> MarkerId {
+        let id = marker.id.clone();
+        self.markers.push(marker);
+        self.sort_markers();
+        id
+    }
+
+    /// Remove a sequence marker by ID
+    pub fn remove_marker(&mut self, marker_id: &MarkerId) -> Option<Marker> {
+        let index = self.markers.iter().position(|m| &m.id == marker_id)?;
+        Some(self.markers.remove(index))
+    }
+
+    /// Get mutable sequence marker by ID
+    pub fn get_marker_mut(&mut self, marker_id: &MarkerId) -> Option<&mut Marker> {
+        self.markers.iter_mut().find(|m| &m.id == marker_id)
+    }
+
+    /// Move a sequence marker to a new time
+    pub fn move_marker(&mut self, marker_id: &MarkerId, time: Timecode) -> Result<()> {
+        let marker = self
+            .get_marker_mut(marker_id)
+            .ok_or_else(|| VxError::NotFound("Marker".to_string()))?;
+        marker.time = time;
+        self.sort_markers();
+        Ok(())
+    }
+
+    fn sort_markers(&mut self) {
+        self.markers.sort_by_key(|m| m.time);
+    }
+
+    /// Sequence markers and clip markers in timeline time, sorted by time
+    pub fn timeline_markers(&self) -> Vec<(Timecode, &Marker)> {
+        let mut markers: Vec<_> = self
+            .markers
+            .iter()
+            .map(|m| (m.time, m))
+            .chain(
+                self.video_tracks
+                    .iter()
+                    .chain(self.audio_tracks.iter())
+                    .flat_map(|t| t.clips().iter())
+                    .flat_map(|c| c.timeline_markers()),
+            )
+            .collect();
+        markers.sort_by_key(|(time, _)| *time);
+        markers
+    }
+
+    /// First marker strictly after the given time
+    pub fn next_marker(&self, from: Timecode) -> Option<Timecode> {
+        self.timeline_markers()
+            .into_iter()
+            .map(|(time, _)| time)
+            .find(|time| *time > from)
+    }
+
+    /// Last marker strictly before the given time
+    pub fn previous_marker(&self, from: Timecode) -> Option<Timecode> {
+        self.timeline_markers()
+            .into_iter()
+            .map(|(time, _)| time)
+            .rfind(|time| *time < from)
+    }
+
+    /// Move the playhead to the next marker, returning the new position
+    pub fn seek_next_marker(&mut self) -> Option<Timecode> {
+        let time = self.next_marker(self.playhead)?;
+        self.playhead = time;
+        Some(time)
+    }
+
+    /// Move the playhead to the previous marker, returning the new position
+    pub fn seek_previous_marker(&mut self) -> Option<Timecode> {
+        let time = self.previous_marker(self.playhead)?;
+        self.playhead = time;
+        Some(time)
+    }
+
+    /// Chapters for export. Each chapter runs until the next one or the end of the sequence.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let end = self.duration();
+        let points: Vec<_> = self
+            .timeline_markers()
+            .into_iter()
+            .filter(|(time, marker)| marker.is_chapter() && *time < end)
+            .collect();
+
+        points
+            .iter()
+            .enumerate()
+            .map(|(index, (start, marker))| Chapter {
+                title: marker.name.clone(),
+                start: *start,
+                end: points.get(index + 1).map(|(next, _)| *next).unwrap_or(end),
+            })
+            .collect()
+    }
+
+    /// Shift sequence markers after a ripple edit at `edit_point` that removed `removed`
+    /// and inserted `inserted` of timeline time. Markers inside the removed range are
+    /// deleted and returned.
+    pub fn ripple_markers(
+        &mut self,
+        edit_point: Timecode,
+        removed: Duration,
+        inserted: Duration,
+    ) -> Vec<Marker> {
+        let removed_end = edit_point.as_duration() + removed;
+        let (deleted, kept): (Vec<_>, Vec<_>) = self
+            .markers
+            .drain(..)
+            .partition(|m| m.time >= edit_point && m.time.as_duration() < removed_end);
+
+        self.markers = kept
+            .into_iter()
+            .map(|mut m| {
+                if m.time.as_duration() >= removed_end {
+                    m.time = Timecode(m.time.as_duration() - removed + inserted);
+                }
+                m
+            })
+            .collect();
+        self.sort_markers();
+        deleted
+    }
+
+    /// Remove a clip and close the hole it leaves, rippling later clips on every
+    /// unlocked track and sequence markers so the tracks stay in sync. Fails if
+    /// another unlocked track has clips inside the hole.
+    pub fn ripple_delete_clip(&mut self, track_id: TrackId, clip_id: &ClipId) -> Result<Clip> {
+        let track = self
+            .get_track(track_id)
+            .ok_or_else(|| VxError::NotFound("Track".to_string()))?;
+        if track.locked {
+            return Err(VxError::Timeline(format!(
+                "Track '{}' is locked",
+                track.name
+            )));
+        }
+        let range = track
+            .get_clip(clip_id)
+            .ok_or_else(|| VxError::NotFound("Clip".to_string()))?
+            .timeline_range();
+
+        let blocking = self
+            .video_tracks
+            .iter()
+            .chain(self.audio_tracks.iter())
+            .filter(|t| !t.locked && t.id() != track_id)
+            .find(|t| !t.clips_in_range(range).is_empty());
+        if let Some(track) = blocking {
+            return Err(VxError::Timeline(format!(
+                "Track '{}' has clips inside the deleted range",
+                track.name
+            )));
+        }
+
+        let clip = self
+            .get_track_mut(track_id)
+            .and_then(|track| track.remove_clip(clip_id))
+            .ok_or_else(|| VxError::NotFound("Clip".to_string()))?;
+        for track in self
+            .video_tracks
+            .iter_mut()
+            .chain(self.audio_tracks.iter_mut())
+            .filter(|t| !t.locked)
+        {
+            track.shift_clips_after(range.end(), range.duration);
+        }
+        self.ripple_markers(range.start, range.duration, Duration::ZERO);
+        Ok(clip)
+    }
+
+    /// Freeze the frame shown at `at` on a track for `duration`. The clip under
+    /// `at` is split, and the freeze frame is inserted between the two parts. Like
+    /// an insert edit, this pushes later clips on all unlocked tracks and sequence
+    /// markers back, so other tracks stay in sync.
+    pub fn insert_freeze_frame(
+        &mut self,
+        track_id: TrackId,
+        at: Timecode,
+        duration: Duration,
+    ) -> Result<ClipId> {
+        let track = self
+            .get_track(track_id)
+            .ok_or_else(|| VxError::NotFound("Track".to_string()))?;
+        if track.locked {
+            return Err(VxError::Timeline(format!(
+                "Track '{}' is locked",
+                track.name
+            )));
+        }
+
+        let freeze = track
+            .clip_at_time(at)
+            .and_then(|clip| clip.freeze_frame(at, duration))
+            .ok_or_else(|| VxError::NotFound("Clip at freeze time".to_string()))?;
+        let freeze_id = freeze.id.clone();
+        self.ripple_insert(at, duration)?;
+        if let Some(track) = self.get_track_mut(track_id) {
+            track.add_clip(freeze);
+        }
+        Ok(freeze_id)
+    }
+
+    /// Open `duration` of empty time at `at` on every unlocked track: clips crossing
+    /// `at` are split, later clips and sequence markers are pushed back
+    fn ripple_insert(&mut self, at: Timecode, duration: Duration) -> Result<()> {
+        for track in self
+            .video_tracks
+            .iter_mut()
+            .chain(self.audio_tracks.iter_mut())
+            .filter(|t| !t.locked)
+        {
+            if let Some(clip) = track.clip_at_time(at)
+                && clip.timeline_position < at
+            {
+                let clip_id = clip.id.clone();
+                track.split_clip(&clip_id, at)?;
+            }
+            track.push_clips_after(at, duration);
+        }
+        self.ripple_markers(at, Duration::ZERO, duration);
+        Ok(())
+    }
+
+    /// Set the in point (at the playhead if None)
+    pub fn set_in_point(&mut self, time: Option<Timecode>) {
+        self.in_point = Some(time.unwrap_or(self.playhead));
+    }
+
+    /// Set the out point (at the playhead if None)
+    pub fn set_out_point(&mut self, time: Option<Timecode>) {
+        self.out_point = Some(time.unwrap_or(self.playhead));
+    }
+
+    /// Clear both in and out points
+    pub fn clear_in_out(&mut self) {
+        self.in_point = None;
+        self.out_point = None;
+    }
+
+    /// Range between the in and out points. A missing in point means the start of
+    /// the sequence, a missing out point the end. None if neither is set or the
+    /// range is empty.
+    pub fn in_out_range(&self) -> Option<TimeRange> {
+        if self.in_point.is_none() && self.out_point.is_none() {
+            return None;
+        }
+        let start = self.in_point.unwrap_or(Timecode::from_seconds(0.0));
+        let end = self.out_point.unwrap_or_else(|| self.duration());
+        let range = TimeRange::from_bounds(start, end);
+        (!range.is_empty()).then_some(range)
+    }
+
+    /// Range to render: the in/out range if set, otherwise the whole sequence
+    pub fn render_range(&self) -> TimeRange {
+        self.in_out_range().unwrap_or_else(|| {
+            TimeRange::new(Timecode::from_seconds(0.0), self.duration().as_duration())
+        })
+    }
+
+    /// Start times of every frame within a range
+    pub fn frame_times(&self, range: TimeRange) -> Vec<Timecode> {
+        let fps = self.frame_rate.as_f64();
+        // Small epsilon so frame-aligned boundaries are not skipped by rounding
+        let first = (range.start.as_seconds() * fps - 1e-6).ceil().max(0.0) as u64;
+        let last = (range.end().as_seconds() * fps - 1e-6).ceil().max(0.0) as u64;
+        (first..last)
+            .map(|frame| Timecode::from_frames(frame, self.frame_rate))
+            .collect()
+    }
+
+    fn in_out_or_err(&self) -> Result<TimeRange> {
+        self.in_out_range()
+            .ok_or_else(|| VxError::Timeline("No in/out range set".to_string()))
+    }
+
+    /// Remove everything between the in and out points on unlocked tracks, leaving a gap
+    pub fn lift(&mut self) -> Result<()> {
+        let range = self.in_out_or_err()?;
+        self.lift_range(range);
+        Ok(())
+    }
+
+    /// Remove everything between the in and out points on unlocked tracks and close
+    /// the gap, rippling later clips and markers
+    pub fn extract(&mut self) -> Result<()> {
+        let range = self.in_out_or_err()?;
+        self.extract_range(range);
+        self.out_point = Some(range.start);
+        Ok(())
+    }
+
+    /// Remove everything inside `range` on unlocked tracks, leaving a gap
+    pub fn lift_range(&mut self, range: TimeRange) {
+        for track in self
+            .video_tracks
+            .iter_mut()
+            .chain(self.audio_tracks.iter_mut())
+            .filter(|t| !t.locked)
+        {
+            track.lift_range(range);
+        }
+    }
+
+    /// Remove everything inside `range` on unlocked tracks and ripple later clips
+    /// and markers to close the gap
+    pub fn extract_range(&mut self, range: TimeRange) {
+        for track in self
+            .video_tracks
+            .iter_mut()
+            .chain(self.audio_tracks.iter_mut())
+            .filter(|t| !t.locked)
+        {
+            track.lift_range(range);
+            track.shift_clips_after(range.end(), range.duration);
+        }
+        self.ripple_markers(range.start, range.duration, Duration::ZERO);
+    }
+
+    /// Copy the clips inside `range` from every track, trimmed to the range
+    pub fn copy_range(&self, range: TimeRange) -> SequenceClipboard {
+        let copy_tracks = |tracks: &[Track]| -> Vec<ClipboardTrack> {
+            tracks
+                .iter()
+                .enumerate()
+                .filter_map(|(track_index, track)| {
+                    let clips: Vec<Clip> = track
+                        .clone()
+                        .lift_range(range)
+                        .into_iter()
+                        .map(|mut clip| {
+                            clip.timeline_position = Timecode(
+                                clip.timeline_position.as_duration() - range.start.as_duration(),
+                            );
+                            clip
+                        })
+                        .collect();
+                    (!clips.is_empty()).then_some(ClipboardTrack {
+                        track_type: track.track_type,
+                        track_index,
+                        clips,
+                    })
+                })
+                .collect()
+        };
+
+        let mut tracks = copy_tracks(&self.video_tracks);
+        tracks.extend(copy_tracks(&self.audio_tracks));
+        SequenceClipboard {
+            duration: range.duration,
+            tracks,
+        }
+    }
+
+    /// Paste copied clips at `at`, onto the same tracks they were copied from.
+    /// Existing material under the pasted range is overwritten.
+    /// Returns the IDs of the pasted clips. Compound clips are not checked for
+    /// sequence cycles here; [`crate::project::Project::paste`] does that.
+    pub fn paste(&mut self, clipboard: &SequenceClipboard, at: Timecode) -> Result<Vec<ClipId>> {
+        // Validate every target before touching anything
+        for copied in &clipboard.tracks {
+            let track = self
+                .track_at(copied.track_type, copied.track_index)
+                .ok_or_else(|| VxError::NotFound("Paste target track".to_string()))?;
+            if track.locked {
+                return Err(VxError::Timeline(format!(
+                    "Track '{}' is locked",
+                    track.name
+                )));
+            }
+        }
+
+        let range = TimeRange::new(at, clipboard.duration);
+        let mut pasted = Vec::new();
+        for copied in &clipboard.tracks {
+            let tracks = match copied.track_type {
+                TrackType::Video => &mut self.video_tracks,
+                TrackType::Audio => &mut self.audio_tracks,
+            };
+            let track = &mut tracks[copied.track_index];
+            track.lift_range(range);
+            for clip in &copied.clips {
+                let mut clip = clip.clone();
+                clip.id = ClipId::new();
+                clip.timeline_position =
+                    Timecode(at.as_duration() + clip.timeline_position.as_duration());
+                pasted.push(clip.id.clone());
+                track.add_clip(clip);
+            }
+        }
+        Ok(pasted)
+    }
+
+    fn track_at(&self, track_type: TrackType, index: usize) -> Option<&Track> {
+        self.tracks(track_type).get(index)
+    }
+
+    /// Place source clips at `at` on the targeted tracks, at most one clip per
+    /// track type. Insert edits split clips crossing `at` and push everything
+    /// after it later on all unlocked tracks. Returns the IDs of the placed clips.
+    /// Compound clips are not checked for sequence cycles here;
+    /// [`crate::project::Project::edit`] does that.
+    pub fn edit(
+        &mut self,
+        clips: Vec<(TrackType, Clip)>,
+        at: Timecode,
+        mode: EditMode,
+    ) -> Result<Vec<ClipId>> {
+        // Validate every target before touching anything
+        let mut placements = Vec::new();
+        for (track_type, clip) in clips {
+            let track_id = self
+                .targets
+                .get(track_type)
+                .ok_or_else(|| VxError::Timeline(format!("No {track_type:?} track is targeted")))?;
+            if placements.iter().any(|(id, _)| *id == track_id) {
+                return Err(VxError::InvalidParameter(format!(
+                    "More than one {track_type:?} clip in a single edit"
+                )));
+            }
+            let track = self
+                .get_track(track_id)
+                .ok_or_else(|| VxError::NotFound("Target track".to_string()))?;
+            if track.locked {
+                return Err(VxError::Timeline(format!(
+                    "Track '{}' is locked",
+                    track.name
+                )));
+            }
+            placements.push((track_id, clip));
+        }
+
+        if mode == EditMode::Insert {
+            let duration = placements
+                .iter()
+                .map(|(_, clip)| clip.timeline_duration())
+                .max()
+                .unwrap_or_default();
+            self.ripple_insert(at, duration)?;
+        }
+
+        let mut placed = Vec::new();
+        for (track_id, mut clip) in placements {
+            clip.timeline_position = at;
+            let track = self
+                .get_track_mut(track_id)
+                .ok_or_else(|| VxError::NotFound("Target track".to_string()))?;
+            if mode == EditMode::Overwrite {
+                track.lift_range(clip.timeline_range());
+            }
+            placed.push(clip.id.clone());
+            track.add_clip(clip);
+        }
+        Ok(placed)
+    }
+
+    /// Ranges that are empty on all of the given tracks. Closing these keeps the
+    /// tracks in sync.
+    pub fn gaps(&self, track_ids: &[TrackId]) -> Result<Vec<TimeRange>> {
+        let tracks = track_ids
+            .iter()
+            .map(|id| {
+                self.get_track(*id)
+                    .ok_or_else(|| VxError::NotFound("Track".to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(find_gaps(&tracks))
+    }
+
+    /// Close a gap on the given tracks together, rippling later clips on all of them.
+    /// The gap must be empty on every track. Sequence markers ripple too when every
+    /// track of the sequence takes part.
+    pub fn close_gap(&mut self, track_ids: &[TrackId], gap: TimeRange) -> Result<()> {
+        let gaps = self.gaps(track_ids)?;
+        if !gaps.iter().any(|g| g.contains_range(gap)) {
+            return Err(VxError::Timeline(
+                "Range is not empty on all tracks".to_string(),
+            ));
+        }
+
+        for track_id in track_ids {
+            if let Some(track) = self.get_track(*track_id)
+                && track.locked
+            {
+                return Err(VxError::Timeline(format!(
+                    "Track '{}' is locked",
+                    track.name
+                )));
+            }
+        }
+
+        for track_id in track_ids {
+            if let Some(track) = self.get_track_mut(*track_id) {
+                track.shift_clips_after(gap.end(), gap.duration);
+            }
+        }
+
+        let all_tracks = self
+            .video_tracks
+            .iter()
+            .chain(self.audio_tracks.iter())
+            .all(|t| track_ids.contains(&t.id()));
+        if all_tracks {
+            self.ripple_markers(gap.start, gap.duration, Duration::ZERO);
+        }
+        Ok(())
+    }
+
+    /// Close every gap that is empty on all of the given tracks
+    pub fn close_all_gaps(&mut self, track_ids: &[TrackId]) -> Result<()> {
+        // Close from the end so earlier gap ranges stay valid
+        for gap in self.gaps(track_ids)?.into_iter().rev() {
+            self.close_gap(track_ids, gap)?;
+        }
+        Ok(())
+    }
+
+    /// Sequences referenced directly by compound clips in this sequence
+    pub fn nested_sequence_ids(&self) -> HashSet<SequenceId> {
+        self.video_tracks
+            .iter()
+            .chain(self.audio_tracks.iter())
+            .flat_map(|t| t.clips().iter())
+            .filter_map(|c| c.nested_sequence().cloned())
             .collect()
     }
+
+    /// Move the given clips into a new sequence and replace them with a single compound clip.
+    ///
+    /// Track layout is preserved inside the new sequence and clips keep their relative
+    /// offsets. The compound clip is placed on the lowest video track that held a selected
+    /// clip (or the lowest audio track for audio-only selections).
+    /// Returns the new sequence and the ID of the compound clip.
+    pub fn nest_clips(&mut self, clip_ids: &[ClipId], name: String) -> Result<(Sequence, ClipId)> {
+        if clip_ids.is_empty() {
+            return Err(VxError::InvalidParameter("No clips selected".to_string()));
+        }
+
+        let selected: Vec<&Clip> = self
+            .video_tracks
+            .iter()
+            .chain(self.audio_tracks.iter())
+            .flat_map(|t| t.clips().iter())
+            .filter(|c| clip_ids.contains(&c.id))
+            .collect();
+        if selected.len() != clip_ids.len() {
+            return Err(VxError::NotFound("Selected clip".to_string()));
+        }
+
+        let start = selected
+            .iter()
+            .map(|c| c.timeline_position)
+            .min()
+            .unwrap_or(Timecode::from_seconds(0.0));
+        let end = selected
+            .iter()
+            .map(|c| c.timeline_end())
+            .max()
+            .unwrap_or(start);
+
+        let involved = |t: &&Track| t.clips().iter().any(|c| clip_ids.contains(&c.id));
+        let target_id = self
+            .video_tracks
+            .iter()
+            .find(involved)
+            .or_else(|| self.audio_tracks.iter().find(involved))
+            .map(|t| t.id())
+            .ok_or_else(|| VxError::NotFound("Selected clip".to_string()))?;
+
+        for track in self.video_tracks.iter().chain(self.audio_tracks.iter()) {
+            if track.locked && track.clips().iter().any(|c| clip_ids.contains(&c.id)) {
+                return Err(VxError::Timeline(format!(
+                    "Track '{}' is locked",
+                    track.name
+                )));
+            }
+        }
+
+        // The compound clip needs the whole selection span free on the target track
+        if let Some(track) = self.get_track(target_id) {
+            let span = TimeRange::from_bounds(start, end);
+            let blocked = track
+                .clips_in_range(span)
+                .iter()
+                .any(|c| !clip_ids.contains(&c.id));
+            if blocked {
+                return Err(VxError::Timeline(format!(
+                    "Unselected clips on track '{}' overlap the selection",
+                    track.name
+                )));
+            }
+        }
+
+        let mut nested = Sequence::new(name.clone(), self.frame_rate, self.resolution);
+        for track in self
+            .video_tracks
+            .iter_mut()
+            .chain(self.audio_tracks.iter_mut())
+        {
+            if !track.clips().iter().any(|c| clip_ids.contains(&c.id)) {
+                continue;
+            }
+
+            // Transitions entirely inside the selection move with the clips
+            let transitions: Vec<_> = track
+                .transitions
+                .iter()
+                .filter(|t| clip_ids.contains(&t.outgoing) && clip_ids.contains(&t.incoming))
+                .cloned()
+                .collect();
+
+            let mut nested_track = Track::new(track.id(), track.name.clone(), track.track_type);
+            for clip_id in clip_ids {
+                if let Some(mut clip) = track.remove_clip(clip_id) {
+                    clip.timeline_position =
+                        Timecode(clip.timeline_position.as_duration() - start.as_duration());
+                    nested_track.add_clip(clip);
+                }
+            }
+            nested_track.transitions = transitions;
+            nested.add_track(nested_track);
+        }
+
+        let compound = Clip::new_compound(
+            name,
+            nested.id.clone(),
+            start,
+            end.as_duration() - start.as_duration(),
+        );
+        let compound_id = compound.id.clone();
+        if let Some(track) = self.get_track_mut(target_id) {
+            track.add_clip(compound);
+        }
+
+        Ok((nested, compound_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::MediaId;
+
+    fn clip(position: f64, duration: f64) -> Clip {
+        Clip::new(
+            "clip".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(position),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(duration),
+        )
+    }
+
+    #[test]
+    fn test_nest_clips() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut v1 = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let mut v2 = Track::new(TrackId(1), "V2".to_string(), TrackType::Video);
+
+        let a = clip(2.0, 3.0);
+        let b = clip(4.0, 4.0);
+        let untouched = clip(10.0, 2.0);
+        let ids = vec![a.id.clone(), b.id.clone()];
+        v1.add_clip(a);
+        v1.add_clip(untouched);
+        v2.add_clip(b);
+        sequence.add_track(v1);
+        sequence.add_track(v2);
+
+        let (nested, compound_id) = sequence.nest_clips(&ids, "nested".to_string()).unwrap();
+
+        assert_eq!(nested.video_tracks.len(), 2);
+        assert_eq!(nested.duration(), Timecode::from_seconds(6.0));
+        assert_eq!(
+            nested.video_tracks[1].clips()[0].timeline_position,
+            Timecode::from_seconds(2.0)
+        );
+
+        let v1 = &sequence.video_tracks[0];
+        assert_eq!(v1.clips().len(), 2);
+        assert_eq!(v1.clips()[0].id, compound_id);
+        assert_eq!(v1.clips()[0].nested_sequence(), Some(&nested.id));
+        assert_eq!(v1.clips()[0].timeline_end(), Timecode::from_seconds(8.0));
+        assert!(sequence.video_tracks[1].clips().is_empty());
+    }
+
+    #[test]
+    fn test_marker_navigation() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let mut c = clip(10.0, 10.0);
+        c.markers
+            .push(Marker::new("clip".to_string(), Timecode::from_seconds(2.0)));
+        track.add_clip(c);
+        sequence.add_track(track);
+
+        sequence.add_marker(Marker::chapter(
+            "Intro".to_string(),
+            Timecode::from_seconds(0.0),
+        ));
+        sequence.add_marker(Marker::chapter(
+            "Part 2".to_string(),
+            Timecode::from_seconds(15.0),
+        ));
+
+        assert_eq!(
+            sequence.seek_next_marker(),
+            Some(Timecode::from_seconds(12.0))
+        );
+        assert_eq!(
+            sequence.seek_next_marker(),
+            Some(Timecode::from_seconds(15.0))
+        );
+        assert_eq!(sequence.seek_next_marker(), None);
+        assert_eq!(
+            sequence.seek_previous_marker(),
+            Some(Timecode::from_seconds(12.0))
+        );
+
+        let chapters = sequence.chapters();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].end, Timecode::from_seconds(15.0));
+        assert_eq!(chapters[1].end, Timecode::from_seconds(20.0));
+    }
+
+    #[test]
+    fn test_ripple_delete_moves_markers() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let first = clip(0.0, 5.0);
+        let first_id = first.id.clone();
+        track.add_clip(first);
+        track.add_clip(clip(5.0, 5.0));
+        sequence.add_track(track);
+
+        sequence.add_marker(Marker::new(
+            "inside".to_string(),
+            Timecode::from_seconds(2.0),
+        ));
+        sequence.add_marker(Marker::new(
+            "after".to_string(),
+            Timecode::from_seconds(7.0),
+        ));
+
+        sequence.ripple_delete_clip(TrackId(0), &first_id).unwrap();
+
+        assert_eq!(
+            sequence.video_tracks[0].clips()[0].timeline_position,
+            Timecode::from_seconds(0.0)
+        );
+        assert_eq!(sequence.markers.len(), 1);
+        assert_eq!(sequence.markers[0].time, Timecode::from_seconds(2.0));
+    }
+
+    #[test]
+    fn test_ripple_delete_keeps_tracks_in_sync() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut video = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let first = clip(0.0, 5.0);
+        let first_id = first.id.clone();
+        video.add_clip(first);
+        video.add_clip(clip(5.0, 5.0));
+        sequence.add_track(video);
+        let mut audio = Track::new(TrackId(1), "A1".to_string(), TrackType::Audio);
+        let music = clip(2.0, 8.0);
+        let music_id = music.id.clone();
+        audio.add_clip(music);
+        sequence.add_track(audio);
+
+        // Closing the hole would cut into the music
+        assert!(sequence.ripple_delete_clip(TrackId(0), &first_id).is_err());
+        assert_eq!(sequence.video_tracks[0].clips().len(), 2);
+
+        sequence.audio_tracks[0].remove_clip(&music_id);
+        sequence.audio_tracks[0].add_clip(clip(6.0, 4.0));
+        sequence.add_marker(Marker::new(
+            "after".to_string(),
+            Timecode::from_seconds(6.0),
+        ));
+        sequence.ripple_delete_clip(TrackId(0), &first_id).unwrap();
+        assert_eq!(
+            sequence.audio_tracks[0].clips()[0].timeline_position,
+            Timecode::from_seconds(1.0)
+        );
+        assert_eq!(sequence.markers[0].time, Timecode::from_seconds(1.0));
+    }
+
+    #[test]
+    fn test_insert_freeze_frame_keeps_tracks_in_sync() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut video = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        video.add_clip(clip(0.0, 10.0));
+        sequence.add_track(video);
+        let mut audio = Track::new(TrackId(1), "A1".to_string(), TrackType::Audio);
+        audio.add_clip(clip(0.0, 10.0));
+        sequence.add_track(audio);
+        sequence.add_marker(Marker::new(
+            "later".to_string(),
+            Timecode::from_seconds(7.0),
+        ));
+
+        sequence
+            .insert_freeze_frame(
+                TrackId(0),
+                Timecode::from_seconds(2.0),
+                Duration::from_secs(3),
+            )
+            .unwrap();
+
+        // The audio opens the same hole, so the clip resuming at 5s still lines up
+        let audio = &sequence.audio_tracks[0];
+        assert_eq!(audio.clips().len(), 2);
+        assert_eq!(
+            audio.clips()[1].timeline_position,
+            Timecode::from_seconds(5.0)
+        );
+        assert_eq!(audio.clips()[1].source_in, Timecode::from_seconds(2.0));
+        assert_eq!(sequence.markers[0].time, Timecode::from_seconds(10.0));
+        assert_eq!(sequence.duration(), Timecode::from_seconds(13.0));
+    }
+
+    #[test]
+    fn test_insert_freeze_frame() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(clip(0.0, 5.0));
+        track.add_clip(clip(5.0, 5.0));
+        sequence.add_track(track);
+        sequence.add_marker(Marker::new(
+            "later".to_string(),
+            Timecode::from_seconds(7.0),
+        ));
+
+        let freeze_id = sequence
+            .insert_freeze_frame(
+                TrackId(0),
+                Timecode::from_seconds(2.0),
+                Duration::from_secs(3),
+            )
+            .unwrap();
+
+        let track = &sequence.video_tracks[0];
+        assert_eq!(track.clips().len(), 4);
+        let freeze = track.get_clip(&freeze_id).unwrap();
+        assert!(freeze.is_freeze_frame());
+        assert_eq!(freeze.timeline_end(), Timecode::from_seconds(5.0));
+        for t in [2.0, 3.5, 4.9] {
+            assert_eq!(
+                freeze.timeline_to_source_time(Timecode::from_seconds(t)),
+                Some(Timecode::from_seconds(2.0))
+            );
+        }
+
+        // The rest of the original clip resumes after the hold
+        assert_eq!(
+            track.clips()[2].timeline_position,
+            Timecode::from_seconds(5.0)
+        );
+        assert_eq!(track.clips()[2].source_in, Timecode::from_seconds(2.0));
+        assert_eq!(
+            track.clips()[3].timeline_position,
+            Timecode::from_seconds(8.0)
+        );
+        assert_eq!(sequence.markers[0].time, Timecode::from_seconds(10.0));
+    }
+
+    #[test]
+    fn test_track_management() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let v1 = sequence.new_track(TrackType::Video, "V1".to_string());
+        let v2 = sequence.new_track(TrackType::Video, "V2".to_string());
+        let a1 = sequence.new_track(TrackType::Audio, "A1".to_string());
+        assert_ne!(v1, v2);
+        assert_ne!(v2, a1);
+
+        // A caller-chosen ID that is already taken gets replaced
+        let added = sequence.add_track(Track::new(v1, "V3".to_string(), TrackType::Video));
+        assert_ne!(added, v1);
+
+        let inserted = sequence
+            .insert_track(TrackType::Video, 0, "Bottom".to_string())
+            .unwrap();
+        assert_eq!(sequence.video_tracks[0].id(), inserted);
+        assert!(
+            sequence
+                .insert_track(TrackType::Audio, 5, "A9".to_string())
+                .is_err()
+        );
+
+        sequence.raise_track(inserted).unwrap();
+        assert_eq!(sequence.video_tracks[1].id(), inserted);
+        sequence.move_track(inserted, 3).unwrap();
+        assert_eq!(sequence.video_tracks[3].id(), inserted);
+        sequence.raise_track(inserted).unwrap();
+        assert_eq!(sequence.video_tracks[3].id(), inserted);
+
+        sequence.target_track(v2).unwrap();
+        sequence.remove_track(v2).unwrap();
+        assert_eq!(sequence.targets.video, None);
+        assert!(sequence.get_track(v2).is_none());
+
+        // Deleted IDs are not handed out again
+        let next = sequence.new_track(TrackType::Video, "V4".to_string());
+        assert!([v1, v2, a1, added, inserted].iter().all(|id| *id != next));
+    }
+
+    #[test]
+    fn test_insert_and_overwrite_edits() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let v1 = sequence.new_track(TrackType::Video, "V1".to_string());
+        let a1 = sequence.new_track(TrackType::Audio, "A1".to_string());
+        sequence
+            .get_track_mut(v1)
+            .unwrap()
+            .add_clip(clip(0.0, 10.0));
+        sequence
+            .get_track_mut(a1)
+            .unwrap()
+            .add_clip(clip(0.0, 10.0));
+
+        assert!(
+            sequence
+                .edit(
+                    vec![(TrackType::Video, clip(0.0, 2.0))],
+                    Timecode::from_seconds(4.0),
+                    EditMode::Insert
+                )
+                .is_err()
+        );
+        sequence.target_track(v1).unwrap();
+
+        sequence
+            .edit(
+                vec![(TrackType::Video, clip(0.0, 2.0))],
+                Timecode::from_seconds(4.0),
+                EditMode::Insert,
+            )
+            .unwrap();
+        // Both tracks were split and pushed to stay in sync
+        let v = &sequence.video_tracks[0];
+        assert_eq!(v.clips().len(), 3);
+        assert_eq!(v.clips()[2].timeline_position, Timecode::from_seconds(6.0));
+        assert_eq!(sequence.audio_tracks[0].clips().len(), 2);
+        assert_eq!(sequence.duration(), Timecode::from_seconds(12.0));
+
+        sequence
+            .edit(
+                vec![(TrackType::Video, clip(0.0, 3.0))],
+                Timecode::from_seconds(1.0),
+                EditMode::Overwrite,
+            )
+            .unwrap();
+        let v = &sequence.video_tracks[0];
+        assert_eq!(v.clips().len(), 4);
+        assert_eq!(v.clips()[2].timeline_position, Timecode::from_seconds(4.0));
+        assert_eq!(sequence.duration(), Timecode::from_seconds(12.0));
+    }
+
+    #[test]
+    fn test_solo_and_visibility() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let v1 = sequence.new_track(TrackType::Video, "V1".to_string());
+        let a1 = sequence.new_track(TrackType::Audio, "A1".to_string());
+        let a2 = sequence.new_track(TrackType::Audio, "A2".to_string());
+        let a3 = sequence.new_track(TrackType::Audio, "A3".to_string());
+        for id in [v1, a1, a2, a3] {
+            sequence.get_track_mut(id).unwrap().add_clip(clip(0.0, 5.0));
+        }
+        let at = Timecode::from_seconds(1.0);
+        let audible = |s: &Sequence| s.audible_tracks().map(|t| t.id()).collect::<Vec<_>>();
+
+        assert_eq!(audible(&sequence), vec![a1, a2, a3]);
+        sequence.set_solo(a1, true, false).unwrap();
+        sequence.set_solo(a2, true, false).unwrap();
+        assert_eq!(audible(&sequence), vec![a1, a2]);
+        sequence.set_solo(a3, true, true).unwrap();
+        assert_eq!(audible(&sequence), vec![a3]);
+
+        // Mute wins over solo
+        sequence.get_track_mut(a3).unwrap().muted = true;
+        assert!(sequence.audio_clips_at_time(at).is_empty());
+        assert!(sequence.set_solo(v1, true, false).is_err());
+
+        assert_eq!(sequence.video_clips_at_time(at).len(), 1);
+        sequence.get_track_mut(v1).unwrap().visible = false;
+        assert!(sequence.video_clips_at_time(at).is_empty());
+    }
+
+    fn range_sequence() -> Sequence {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut v1 = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let mut a1 = Track::new(TrackId(1), "A1".to_string(), TrackType::Audio);
+        v1.add_clip(clip(0.0, 10.0));
+        v1.add_clip(clip(10.0, 10.0));
+        a1.add_clip(clip(0.0, 20.0));
+        sequence.add_track(v1);
+        sequence.add_track(a1);
+        sequence.set_in_point(Some(Timecode::from_seconds(5.0)));
+        sequence.set_out_point(Some(Timecode::from_seconds(12.0)));
+        sequence
+    }
+
+    #[test]
+    fn test_lift_leaves_gap() {
+        let mut sequence = range_sequence();
+        sequence.lift().unwrap();
+
+        let v1 = &sequence.video_tracks[0];
+        assert_eq!(v1.clips().len(), 2);
+        assert_eq!(v1.clips()[0].timeline_end(), Timecode::from_seconds(5.0));
+        assert_eq!(
+            v1.clips()[1].timeline_position,
+            Timecode::from_seconds(12.0)
+        );
+
+        // The audio clip spanning the range is split in two
+        assert_eq!(sequence.audio_tracks[0].clips().len(), 2);
+        assert_eq!(sequence.duration(), Timecode::from_seconds(20.0));
+    }
+
+    #[test]
+    fn test_extract_closes_gap() {
+        let mut sequence = range_sequence();
+        sequence.extract().unwrap();
+
+        let v1 = &sequence.video_tracks[0];
+        assert_eq!(v1.clips()[1].timeline_position, Timecode::from_seconds(5.0));
+        assert_eq!(v1.clips()[1].source_in, Timecode::from_seconds(2.0));
+        assert_eq!(sequence.duration(), Timecode::from_seconds(13.0));
+    }
+
+    #[test]
+    fn test_copy_paste_range() {
+        let mut sequence = range_sequence();
+        let range = sequence.in_out_range().unwrap();
+        let clipboard = sequence.copy_range(range);
+
+        assert_eq!(clipboard.tracks.len(), 2);
+        assert_eq!(clipboard.tracks[0].clips.len(), 2);
+        assert_eq!(
+            clipboard.tracks[0].clips[1].timeline_position,
+            Timecode::from_seconds(5.0)
+        );
+
+        let pasted = sequence
+            .paste(&clipboard, Timecode::from_seconds(30.0))
+            .unwrap();
+        assert_eq!(pasted.len(), 3);
+        assert_eq!(sequence.duration(), Timecode::from_seconds(37.0));
+        assert_eq!(
+            sequence.video_tracks[0]
+                .clips()
+                .last()
+                .unwrap()
+                .timeline_position,
+            Timecode::from_seconds(35.0)
+        );
+    }
+
+    #[test]
+    fn test_frame_times() {
+        let sequence = range_sequence();
+        let range = TimeRange::new(Timecode::from_seconds(1.0), Duration::from_millis(100));
+        let frames = sequence.frame_times(range);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], Timecode::from_seconds(1.0));
+    }
+
+    #[test]
+    fn test_close_gaps_keeps_sync() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut v1 = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let mut a1 = Track::new(TrackId(1), "A1".to_string(), TrackType::Audio);
+        v1.add_clip(clip(0.0, 2.0));
+        v1.add_clip(clip(5.0, 2.0));
+        v1.add_clip(clip(10.0, 2.0));
+        a1.add_clip(clip(0.0, 3.0));
+        a1.add_clip(clip(10.0, 2.0));
+        sequence.add_track(v1);
+        sequence.add_track(a1);
+
+        assert_eq!(sequence.video_tracks[0].gaps().len(), 2);
+
+        // Only 3-5 and 7-10 are empty on both tracks
+        let ids = [TrackId(0), TrackId(1)];
+        let gaps = sequence.gaps(&ids).unwrap();
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0].start, Timecode::from_seconds(3.0));
+
+        assert!(
+            sequence
+                .close_gap(
+                    &ids,
+                    TimeRange::new(Timecode::from_seconds(2.0), Duration::from_secs(3))
+                )
+                .is_err()
+        );
+
+        sequence.close_all_gaps(&ids).unwrap();
+        assert_eq!(
+            sequence.video_tracks[0].clips()[2].timeline_position,
+            Timecode::from_seconds(5.0)
+        );
+        assert_eq!(
+            sequence.audio_tracks[0].clips()[1].timeline_position,
+            Timecode::from_seconds(5.0)
+        );
+        assert_eq!(
+            sequence.video_tracks[0].gaps(),
+            vec![TimeRange::new(
+                Timecode::from_seconds(2.0),
+                Duration::from_secs(1)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_nest_clips_blocked() {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_30, Resolution::FULL_HD);
+        let mut v1 = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        let mut v2 = Track::new(TrackId(1), "V2".to_string(), TrackType::Video);
+
+        let a = clip(0.0, 2.0);
+        let b = clip(3.0, 2.0);
+        let ids = vec![a.id.clone(), b.id.clone()];
+        v1.add_clip(a);
+        v1.add_clip(clip(2.0, 1.0));
+        v2.add_clip(b);
+        sequence.add_track(v1);
+        sequence.add_track(v2);
+
+        assert!(sequence.nest_clips(&ids, "nested".to_string()).is_err());
+        assert_eq!(sequence.video_tracks[0].clips().len(), 2);
+    }
 }
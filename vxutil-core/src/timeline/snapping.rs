@@ -0,0 +1,226 @@
+//! Snapping for timeline edits
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{ClipId, Sequence};
+use crate::types::Timecode;
+
+/// Kind of point a time can snap to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SnapTargetKind {
+    ClipEdge,
+    Playhead,
+    Marker,
+    InOutPoint,
+    FrameBoundary,
+}
+
+/// A point a candidate time snapped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapTarget {
+    pub time: Timecode,
+    pub kind: SnapTargetKind,
+}
+
+/// Snapping service for dragging clips and edit points
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapper {
+    /// Maximum distance between the candidate time and a snap target
+    pub tolerance: Duration,
+
+    /// Target kinds from highest to lowest priority. Kinds not listed are ignored.
+    pub priority: Vec<SnapTargetKind>,
+}
+
+impl Default for Snapper {
+    fn default() -> Self {
+        Self {
+            tolerance: Duration::from_millis(100),
+            priority: vec![
+                SnapTargetKind::Playhead,
+                SnapTargetKind::Marker,
+                SnapTargetKind::InOutPoint,
+                SnapTargetKind::ClipEdge,
+                SnapTargetKind::FrameBoundary,
+            ],
+        }
+    }
+}
+
+impl Snapper {
+    pub fn new(tolerance: Duration) -> Self {
+        Self {
+            tolerance,
+            ..Default::default()
+        }
+    }
+
+    /// All snap targets of a sequence, ignoring the edges of `exclude`d clips
+    /// (typically the clips being dragged). Frame boundaries are not listed.
+    pub fn targets(&self, sequence: &Sequence, exclude: &[ClipId]) -> Vec<SnapTarget> {
+        let enabled = |kind| self.priority.contains(&kind);
+        let mut targets = Vec::new();
+
+        if enabled(SnapTargetKind::ClipEdge) {
+            for clip in sequence
+                .video_tracks
+                .iter()
+                .chain(sequence.audio_tracks.iter())
+                .flat_map(|t| t.clips().iter())
+                .filter(|c| !exclude.contains(&c.id))
+            {
+                for time in [clip.timeline_position, clip.timeline_end()] {
+                    targets.push(SnapTarget {
+                        time,
+                        kind: SnapTargetKind::ClipEdge,
+                    });
+                }
+            }
+        }
+
+        if enabled(SnapTargetKind::Playhead) {
+            targets.push(SnapTarget {
+                time: sequence.playhead,
+                kind: SnapTargetKind::Playhead,
+            });
+        }
+
+        if enabled(SnapTargetKind::Marker) {
+            for (time, marker) in sequence.timeline_markers() {
+                targets.push(SnapTarget {
+                    time,
+                    kind: SnapTargetKind::Marker,
+                });
+                if let Some(range) = marker.range() {
+                    targets.push(SnapTarget {
+                        time: Timecode(time.as_duration() + range.duration),
+                        kind: SnapTargetKind::Marker,
+                    });
+                }
+            }
+        }
+
+        if enabled(SnapTargetKind::InOutPoint) {
+            for time in [sequence.in_point, sequence.out_point]
+                .into_iter()
+                .flatten()
+            {
+                targets.push(SnapTarget {
+                    time,
+                    kind: SnapTargetKind::InOutPoint,
+                });
+            }
+        }
+
+        targets
+    }
+
+    /// Snap a candidate time. Among targets within the tolerance, the highest-priority
+    /// kind wins and ties are broken by distance. Returns None if nothing is in range.
+    pub fn snap(
+        &self,
+        sequence: &Sequence,
+        candidate: Timecode,
+        exclude: &[ClipId],
+    ) -> Option<SnapTarget> {
+        let mut targets = self.targets(sequence, exclude);
+        if self.priority.contains(&SnapTargetKind::FrameBoundary) {
+            targets.push(SnapTarget {
+                time: nearest_frame_boundary(sequence, candidate),
+                kind: SnapTargetKind::FrameBoundary,
+            });
+        }
+
+        targets
+            .into_iter()
+            .filter(|t| distance(t.time, candidate) <= self.tolerance)
+            .min_by_key(|t| {
+                let rank = self.priority.iter().position(|k| *k == t.kind);
+                (rank, distance(t.time, candidate))
+            })
+    }
+}
+
+fn distance(a: Timecode, b: Timecode) -> Duration {
+    a.as_duration().abs_diff(b.as_duration())
+}
+
+fn nearest_frame_boundary(sequence: &Sequence, time: Timecode) -> Timecode {
+    let frame = (time.as_seconds() * sequence.frame_rate.as_f64()).round();
+    Timecode::from_frames(frame.max(0.0) as u64, sequence.frame_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::MediaId;
+    use crate::timeline::{Clip, Marker, Track, TrackId, TrackType};
+    use crate::types::{FrameRate, Resolution};
+
+    fn sequence() -> Sequence {
+        let mut sequence =
+            Sequence::new("main".to_string(), FrameRate::FPS_25, Resolution::FULL_HD);
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(Clip::new(
+            "clip".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(2.0),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(3.0),
+        ));
+        sequence.add_track(track);
+        sequence.playhead = Timecode::from_seconds(5.05);
+        sequence.add_marker(Marker::new("m".to_string(), Timecode::from_seconds(8.0)));
+        sequence
+    }
+
+    #[test]
+    fn test_snap_priority() {
+        let sequence = sequence();
+        let snapper = Snapper::default();
+
+        // Clip end at 5.0 is closer, but the playhead at 5.05 has priority
+        let target = snapper
+            .snap(&sequence, Timecode::from_seconds(4.98), &[])
+            .unwrap();
+        assert_eq!(target.kind, SnapTargetKind::Playhead);
+
+        let snapper = Snapper {
+            priority: vec![SnapTargetKind::ClipEdge, SnapTargetKind::Playhead],
+            ..Default::default()
+        };
+        let target = snapper
+            .snap(&sequence, Timecode::from_seconds(4.98), &[])
+            .unwrap();
+        assert_eq!(target.time, Timecode::from_seconds(5.0));
+    }
+
+    #[test]
+    fn test_snap_frame_boundary_fallback() {
+        let sequence = sequence();
+        let snapper = Snapper::default();
+
+        let target = snapper
+            .snap(&sequence, Timecode::from_seconds(10.03), &[])
+            .unwrap();
+        assert_eq!(target.kind, SnapTargetKind::FrameBoundary);
+        assert_eq!(target.time, Timecode::from_seconds(10.04));
+    }
+
+    #[test]
+    fn test_snap_excludes_dragged_clip() {
+        let sequence = sequence();
+        let clip_id = sequence.video_tracks[0].clips()[0].id.clone();
+        let snapper = Snapper {
+            priority: vec![SnapTargetKind::ClipEdge],
+            ..Default::default()
+        };
+
+        assert!(
+            snapper
+                .snap(&sequence, Timecode::from_seconds(2.0), &[clip_id])
+                .is_none()
+        );
+    }
+}
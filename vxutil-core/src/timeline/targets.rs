@@ -0,0 +1,48 @@
+//! Source patching - which tracks receive new clips in insert and overwrite edits
+
+use serde::{Deserialize, Serialize};
+
+use super::{TrackId, TrackType};
+
+/// How an edit makes room for the clips it places
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditMode {
+    /// Push everything at and after the edit point later on all unlocked tracks
+    Insert,
+    /// Replace whatever lies under the new clips
+    Overwrite,
+}
+
+/// Destination tracks for the video and audio of source clips
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TrackTargets {
+    pub video: Option<TrackId>,
+    pub audio: Option<TrackId>,
+}
+
+impl TrackTargets {
+    /// Targeted track for a track type
+    pub fn get(&self, track_type: TrackType) -> Option<TrackId> {
+        match track_type {
+            TrackType::Video => self.video,
+            TrackType::Audio => self.audio,
+        }
+    }
+
+    pub fn set(&mut self, track_type: TrackType, track_id: Option<TrackId>) {
+        match track_type {
+            TrackType::Video => self.video = track_id,
+            TrackType::Audio => self.audio = track_id,
+        }
+    }
+
+    /// Untarget a track (e.g. when it is deleted)
+    pub fn clear(&mut self, track_id: TrackId) {
+        if self.video == Some(track_id) {
+            self.video = None;
+        }
+        if self.audio == Some(track_id) {
+            self.audio = None;
+        }
+    }
+}
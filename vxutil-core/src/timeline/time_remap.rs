@@ -0,0 +1,196 @@
+//! Time remapping - variable playback speed within a clip
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::animation::{Interpolation, Keyframe, KeyframeCurve};
+use crate::types::Timecode;
+
+/// Linear keys standing in for the rest of an eased segment that cannot be split exactly
+const REMAINDER_SAMPLES: u32 = 16;
+
+/// How source frames are picked when playback speed is not 1:1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RetimeMode {
+    /// Show the nearest source frame
+    #[default]
+    FrameSampling,
+    /// Mix the two neighbouring source frames by the position between them
+    FrameBlending,
+    /// Blend while playing slower than real time, sample otherwise
+    Auto,
+}
+
+impl RetimeMode {
+    /// Whether frames should be blended at the given playback speed
+    pub fn blends_at(&self, speed: f64) -> bool {
+        match self {
+            RetimeMode::FrameSampling => false,
+            RetimeMode::FrameBlending => true,
+            RetimeMode::Auto => speed.abs() < 1.0,
+        }
+    }
+}
+
+/// Keyframed playback speed over a clip.
+///
+/// The source position at a given clip time is the integral of the speed curve
+/// up to that time: 1.0 plays normally, 0.0 freezes and negative values play
+/// backwards. Speed keyframes are in clip time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeRemap {
+    pub speed: KeyframeCurve,
+
+    /// Length of the clip on the timeline
+    pub duration: Duration,
+}
+
+impl TimeRemap {
+    /// Remap playing at normal speed for `duration`
+    pub fn new(duration: Duration) -> Self {
+        let mut speed = KeyframeCurve::new();
+        speed.set_keyframe(Keyframe::new(
+            Timecode::from_seconds(0.0),
+            1.0,
+            Interpolation::Linear,
+        ));
+        Self { speed, duration }
+    }
+
+    /// Remap holding the source frame at the clip start for `duration`
+    pub fn frozen(duration: Duration) -> Self {
+        let mut speed = KeyframeCurve::new();
+        speed.set_keyframe(Keyframe::new(
+            Timecode::from_seconds(0.0),
+            0.0,
+            Interpolation::Hold,
+        ));
+        Self { speed, duration }
+    }
+
+    /// Whether the speed is zero throughout, showing a single frame
+    pub fn is_frozen(&self) -> bool {
+        !self.speed.is_empty() && self.speed.keyframes().iter().all(|k| k.value == 0.0)
+    }
+
+    /// Set the speed at a clip time
+    pub fn set_speed(&mut self, time: Timecode, speed: f32, interpolation: Interpolation) {
+        self.speed
+            .set_keyframe(Keyframe::new(time, speed, interpolation));
+    }
+
+    /// Playback speed at a clip time
+    pub fn speed_at(&self, clip_time: Timecode) -> f64 {
+        self.speed.value_at(clip_time).map_or(1.0, f64::from)
+    }
+
+    /// Source offset in seconds reached at a clip time (negative when reversed
+    /// past the start)
+    pub fn source_offset(&self, clip_time: Timecode) -> f64 {
+        self.speed
+            .integral(clip_time)
+            .unwrap_or_else(|| clip_time.as_seconds())
+    }
+
+    /// Cut this remap at a clip time, keeping the part before it.
+    /// Returns the part after it, with keyframes shifted to start at zero. The
+    /// tail plays exactly as the same stretch of the original: an eased segment
+    /// crossing the cut continues from the point on its curve where it was cut.
+    pub fn split_at(&mut self, clip_time: Timecode) -> TimeRemap {
+        let offset = clip_time.as_duration().min(self.duration);
+
+        let mut speed = KeyframeCurve::new();
+        if let Some(value) = self.speed.value_at(clip_time) {
+            // Pin the current speed so the tail starts where the cut happened
+            let interpolation = self.rest_of_segment(clip_time, &mut speed, offset);
+            speed.set_keyframe(Keyframe::new(
+                Timecode::from_seconds(0.0),
+                value,
+                interpolation,
+            ));
+        }
+        for keyframe in self.speed.keyframes().iter().filter(|k| k.time > clip_time) {
+            speed.set_keyframe(Keyframe {
+                time: Timecode(keyframe.time.as_duration() - offset),
+                ..*keyframe
+            });
+        }
+
+        let tail = TimeRemap {
+            speed,
+            duration: self.duration - offset,
+        };
+        self.duration = offset;
+        tail
+    }
+
+    /// Interpolation from a cut at `clip_time` to the next keyframe. An eased
+    /// segment is split on its curve; if that is not possible, linear keys
+    /// following the rest of the segment are added to `tail`, shifted back by `offset`.
+    fn rest_of_segment(
+        &self,
+        clip_time: Timecode,
+        tail: &mut KeyframeCurve,
+        offset: Duration,
+    ) -> Interpolation {
+        let keyframes = self.speed.keyframes();
+        let next_index = keyframes.partition_point(|k| k.time <= clip_time);
+        let Some(from) = next_index.checked_sub(1).map(|index| &keyframes[index]) else {
+            return Interpolation::Linear;
+        };
+        let (Interpolation::Bezier(curve), Some(to)) =
+            (from.interpolation, keyframes.get(next_index))
+        else {
+            return from.interpolation;
+        };
+
+        let span = to.time.as_duration() - from.time.as_duration();
+        let elapsed = clip_time.as_duration() - from.time.as_duration();
+        if let Some(rest) = curve.split_at(elapsed.as_secs_f64() / span.as_secs_f64()) {
+            return Interpolation::Bezier(rest);
+        }
+
+        let remaining = to.time.as_duration() - clip_time.as_duration();
+        for step in 1..REMAINDER_SAMPLES {
+            let time = Timecode(clip_time.as_duration() + remaining * step / REMAINDER_SAMPLES);
+            if let Some(value) = self.speed.value_at(time) {
+                tail.set_keyframe(Keyframe::new(
+                    Timecode(time.as_duration() - offset),
+                    value,
+                    Interpolation::Linear,
+                ));
+            }
+        }
+        Interpolation::Linear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Easing;
+
+    #[test]
+    fn test_split_keeps_eased_ramp() {
+        // 1x to 3x over 4s with ease-in-out, cut 1s into the ramp
+        let mut remap = TimeRemap::new(Duration::from_secs(6));
+        remap.set_speed(Timecode::from_seconds(0.0), 1.0, Easing::EaseInOut.into());
+        remap.set_speed(Timecode::from_seconds(4.0), 3.0, Interpolation::Linear);
+        let original = remap.clone();
+
+        let cut = 1.0;
+        let tail = remap.split_at(Timecode::from_seconds(cut));
+        assert_eq!(remap.duration, Duration::from_secs(1));
+        assert_eq!(tail.duration, Duration::from_secs(5));
+
+        let at = |seconds: f64| Timecode::from_seconds(seconds);
+        for t in [0.25, 0.5, 1.0, 2.0, 3.0, 4.0] {
+            let expected = original.source_offset(at(cut + t)) - original.source_offset(at(cut));
+            let actual = tail.source_offset(at(t));
+            assert!(
+                (actual - expected).abs() < 1e-4,
+                "offset at {t}s: {actual} != {expected}"
+            );
+        }
+    }
+}
@@ -1,9 +1,14 @@
 //! Track - a container for clips on the timeline
 
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
 
-use super::{Clip, ClipId};
+use super::index::ClipIndex;
+use super::{BlendMode, Clip, ClipId, Transition, TransitionId};
+use crate::media::MediaLibrary;
 use crate::types::{TimeRange, Timecode};
+use crate::{Result, VxError};
 
 /// Unique identifier for a track
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -19,12 +24,46 @@ pub enum TrackType {
 /// A track contains multiple clips arranged in timeline
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Track {
-    pub id: TrackId,
+    /// Unique within a sequence; [`super::Sequence::add_track`] reassigns it on a clash
+    id: TrackId,
     pub name: String,
     pub track_type: TrackType,
-    pub clips: Vec<Clip>,
+
+    /// Clips sorted by timeline position. Private so every edit goes through a
+    /// method that keeps the lookup index current.
+    clips: Vec<Clip>,
+
+    /// Transitions at edit points between adjacent clips
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+
     pub muted: bool,
     pub locked: bool,
+
+    /// Audio only: while any audio track is soloed, only soloed tracks are heard
+    #[serde(default)]
+    pub solo: bool,
+
+    /// Video only: hidden tracks are skipped when compositing
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+
+    /// Video only: opacity of the whole track (0.0 to 1.0)
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+
+    /// Video only: None blends each clip with its own mode. A mode is applied on
+    /// top: the clip is blended with its own mode first, then with the track's.
+    #[serde(default)]
+    pub blend_mode: Option<BlendMode>,
+
+    /// Height of the track in the timeline view, in pixels
+    #[serde(default = "default_height")]
+    pub height: u32,
+
+    /// Lookup index over `clips`, built on first query after an edit
+    #[serde(skip)]
+    index: OnceLock<ClipIndex>,
 }
 
 impl Track {
@@ -34,42 +73,418 @@ impl Track {
             name,
             track_type,
             clips: Vec::new(),
+            transitions: Vec::new(),
             muted: false,
             locked: false,
+            solo: false,
+            visible: true,
+            opacity: 1.0,
+            blend_mode: None,
+            height: Self::DEFAULT_HEIGHT,
+            index: OnceLock::new(),
         }
     }
 
+    pub const DEFAULT_HEIGHT: u32 = 48;
+    pub const MIN_HEIGHT: u32 = 24;
+    pub const MAX_HEIGHT: u32 = 240;
+
+    pub fn id(&self) -> TrackId {
+        self.id
+    }
+
+    /// Only sequences assign IDs, to keep them unique
+    pub(super) fn set_id(&mut self, id: TrackId) {
+        self.id = id;
+    }
+
+    /// Set the track opacity, clamped to 0.0..=1.0
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Set the track height, clamped to the supported range
+    pub fn set_height(&mut self, height: u32) {
+        self.height = height.clamp(Self::MIN_HEIGHT, Self::MAX_HEIGHT);
+    }
+
     /// Add a clip to this track
     pub fn add_clip(&mut self, clip: Clip) {
         self.clips.push(clip);
-        self.sort_clips();
+        self.reindex();
     }
 
-    /// Remove a clip by ID
+    /// Remove a clip by ID (and any transitions attached to it)
     pub fn remove_clip(&mut self, clip_id: &ClipId) -> Option<Clip> {
         if let Some(index) = self.clips.iter().position(|c| &c.id == clip_id) {
+            self.transitions
+                .retain(|t| &t.outgoing != clip_id && &t.incoming != clip_id);
+            self.index.take();
             Some(self.clips.remove(index))
         } else {
             None
         }
     }
 
-    /// Sort clips by timeline position
-    fn sort_clips(&mut self) {
-        self.clips
-            .sort_by(|a, b| a.timeline_position.cmp(&b.timeline_position));
+    /// Clips sorted by timeline position
+    pub fn clips(&self) -> &[Clip] {
+        &self.clips
+    }
+
+    /// Get clip by ID
+    pub fn get_clip(&self, clip_id: &ClipId) -> Option<&Clip> {
+        self.clips.iter().find(|c| &c.id == clip_id)
+    }
+
+    /// Edit a clip in place. The track is re-sorted and transitions that no longer
+    /// fit are dropped afterwards. Returns None if the clip is not on this track.
+    pub fn update_clip<R>(
+        &mut self,
+        clip_id: &ClipId,
+        edit: impl FnOnce(&mut Clip) -> R,
+    ) -> Option<R> {
+        let clip = self.clips.iter_mut().find(|c| &c.id == clip_id)?;
+        let result = edit(clip);
+        self.reindex();
+        self.prune_transitions();
+        Some(result)
+    }
+
+    /// Edit every clip in place, then re-sort and drop transitions that no longer fit
+    pub fn update_clips(&mut self, edit: impl FnMut(&mut Clip)) {
+        self.clips.iter_mut().for_each(edit);
+        self.reindex();
+        self.prune_transitions();
+    }
+
+    /// Attach a transition to the edit point between two adjacent clips.
+    /// Replaces any transition already on that edit point.
+    pub fn add_transition(&mut self, transition: Transition, library: &MediaLibrary) -> Result<()> {
+        if transition.kind.track_type() != self.track_type {
+            return Err(VxError::Timeline(format!(
+                "{} cannot be placed on track '{}'",
+                transition.kind.name(),
+                self.name
+            )));
+        }
+
+        let outgoing = self
+            .get_clip(&transition.outgoing)
+            .ok_or_else(|| VxError::NotFound("Outgoing clip".to_string()))?;
+        let incoming = self
+            .get_clip(&transition.incoming)
+            .ok_or_else(|| VxError::NotFound("Incoming clip".to_string()))?;
+        transition.validate(outgoing, incoming, library)?;
+
+        self.transitions
+            .retain(|t| t.outgoing != transition.outgoing && t.incoming != transition.incoming);
+        self.transitions.push(transition);
+        Ok(())
+    }
+
+    /// Remove a transition by ID
+    pub fn remove_transition(&mut self, transition_id: &TransitionId) -> Option<Transition> {
+        let index = self
+            .transitions
+            .iter()
+            .position(|t| &t.id == transition_id)?;
+        Some(self.transitions.remove(index))
+    }
+
+    /// Timeline range covered by a transition on this track
+    pub fn transition_range(&self, transition: &Transition) -> Option<TimeRange> {
+        let incoming = self.get_clip(&transition.incoming)?;
+        Some(transition.range(incoming.timeline_position))
+    }
+
+    /// Find transition active at given timeline position
+    pub fn transition_at_time(&self, time: Timecode) -> Option<&Transition> {
+        self.transitions.iter().find(|t| {
+            self.transition_range(t)
+                .is_some_and(|range| range.contains(time))
+        })
+    }
+
+    /// Sort clips by timeline position and drop the lookup index
+    fn reindex(&mut self) {
+        self.clips.sort_by_key(|c| c.timeline_position);
+        self.index.take();
+    }
+
+    fn index(&self) -> &ClipIndex {
+        self.index.get_or_init(|| ClipIndex::build(&self.clips))
+    }
+
+    /// End time of the last clip on this track
+    pub fn end(&self) -> Option<Timecode> {
+        self.index().end()
     }
 
     /// Find clip at given timeline position
     pub fn clip_at_time(&self, time: Timecode) -> Option<&Clip> {
-        self.clips.iter().find(|clip| clip.contains_time(time))
+        let candidates = self.index().candidates_at(&self.clips, time);
+        self.clips[candidates]
+            .iter()
+            .find(|clip| clip.contains_time(time))
     }
 
     /// Get all clips that overlap with given time range
     pub fn clips_in_range(&self, range: TimeRange) -> Vec<&Clip> {
-        self.clips
+        let candidates = self.index().candidates_in(&self.clips, range);
+        self.clips[candidates]
             .iter()
             .filter(|clip| clip.overlaps_with(range))
             .collect()
     }
+
+    /// Remove everything inside `range`, leaving a gap. Clips crossing the range
+    /// boundaries are trimmed (or split when they span the whole range).
+    /// Returns the removed portions with their original timeline positions.
+    pub fn lift_range(&mut self, range: TimeRange) -> Vec<Clip> {
+        let (start, end) = (range.start, range.end());
+        let affected: Vec<ClipId> = self
+            .clips_in_range(range)
+            .into_iter()
+            .map(|c| c.id.clone())
+            .collect();
+
+        let mut lifted = Vec::new();
+        let mut kept = Vec::new();
+        for clip_id in affected {
+            let Some(index) = self.clips.iter().position(|c| c.id == clip_id) else {
+                continue;
+            };
+            let mut clip = self.clips.remove(index);
+
+            if clip.timeline_position < start
+                && let Some(right) = clip.split_at(start)
+            {
+                kept.push(clip);
+                clip = right;
+            }
+            if clip.timeline_end() > end
+                && let Some(right) = clip.split_at(end)
+            {
+                kept.push(right);
+            }
+            lifted.push(clip);
+        }
+
+        self.clips.extend(kept);
+        self.reindex();
+        self.prune_transitions();
+        lifted
+    }
+
+    /// Move clips starting at or after `at` earlier by `amount`. Transitions whose
+    /// clips are no longer adjacent are dropped.
+    pub fn shift_clips_after(&mut self, at: Timecode, amount: Duration) {
+        for clip in self.clips.iter_mut().filter(|c| c.timeline_position >= at) {
+            clip.timeline_position =
+                Timecode(clip.timeline_position.as_duration().saturating_sub(amount));
+        }
+        self.reindex();
+        self.prune_transitions();
+    }
+
+    /// Move clips starting at or after `at` later by `amount`. Transitions whose
+    /// clips are no longer adjacent are dropped.
+    pub fn push_clips_after(&mut self, at: Timecode, amount: Duration) {
+        for clip in self.clips.iter_mut().filter(|c| c.timeline_position >= at) {
+            clip.timeline_position = Timecode(clip.timeline_position.as_duration() + amount);
+        }
+        self.reindex();
+        self.prune_transitions();
+    }
+
+    /// Split a clip at a timeline time. Returns the ID of the new right part, which
+    /// takes over any transition leaving the original clip.
+    pub fn split_clip(&mut self, clip_id: &ClipId, time: Timecode) -> Result<ClipId> {
+        let clip = self
+            .clips
+            .iter_mut()
+            .find(|c| &c.id == clip_id)
+            .ok_or_else(|| VxError::NotFound("Clip".to_string()))?;
+        let right = clip.split_at(time).ok_or_else(|| {
+            VxError::InvalidParameter(format!("Split point is not inside clip '{}'", clip.name))
+        })?;
+
+        let right_id = right.id.clone();
+        for transition in self
+            .transitions
+            .iter_mut()
+            .filter(|t| &t.outgoing == clip_id)
+        {
+            transition.outgoing = right_id.clone();
+        }
+        self.add_clip(right);
+        Ok(right_id)
+    }
+
+    /// Lengthen or shorten a still or freeze frame clip. Fails if the clip would run
+    /// into the next clip or the duration is zero; a transition leaving the clip is
+    /// dropped.
+    pub fn set_hold_duration(&mut self, clip_id: &ClipId, duration: Duration) -> Result<()> {
+        let clip = self
+            .get_clip(clip_id)
+            .ok_or_else(|| VxError::NotFound("Clip".to_string()))?;
+        let range = TimeRange::new(clip.timeline_position, duration);
+        if self.clips_in_range(range).iter().any(|c| &c.id != clip_id) {
+            return Err(VxError::Timeline(format!(
+                "Clip '{}' would overlap the next clip",
+                clip.name
+            )));
+        }
+
+        if let Some(clip) = self.clips.iter_mut().find(|c| &c.id == clip_id) {
+            clip.set_hold_duration(duration)?;
+        }
+        self.index.take();
+        self.prune_transitions();
+        Ok(())
+    }
+
+    /// Empty ranges between clips, including a leading gap before the first clip
+    pub fn gaps(&self) -> Vec<TimeRange> {
+        find_gaps(&[self])
+    }
+
+    /// Close a gap by moving every later clip earlier by the gap's duration.
+    /// `gap` must lie entirely within an empty part of the track.
+    pub fn close_gap(&mut self, gap: TimeRange) -> Result<()> {
+        if !self.gaps().iter().any(|g| g.contains_range(gap)) {
+            return Err(VxError::Timeline(format!(
+                "Range is not empty on track '{}'",
+                self.name
+            )));
+        }
+        self.shift_clips_after(gap.end(), gap.duration);
+        Ok(())
+    }
+
+    /// Close every gap on this track, packing clips from the start
+    pub fn close_all_gaps(&mut self) {
+        for gap in self.gaps().into_iter().rev() {
+            self.shift_clips_after(gap.end(), gap.duration);
+        }
+    }
+
+    /// Drop transitions whose clips are gone or no longer adjacent
+    fn prune_transitions(&mut self) {
+        let clips = &self.clips;
+        self.transitions.retain(|t| {
+            let outgoing = clips.iter().find(|c| c.id == t.outgoing);
+            let incoming = clips.iter().find(|c| c.id == t.incoming);
+            matches!((outgoing, incoming), (Some(o), Some(i)) if o.timeline_end() == i.timeline_position)
+        });
+    }
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_height() -> u32 {
+    Track::DEFAULT_HEIGHT
+}
+
+/// Ranges where none of the given tracks has a clip, up to the last clip end
+pub(super) fn find_gaps(tracks: &[&Track]) -> Vec<TimeRange> {
+    let mut occupied: Vec<TimeRange> = tracks
+        .iter()
+        .flat_map(|t| t.clips.iter())
+        .map(|c| c.timeline_range())
+        .filter(|r| !r.is_empty())
+        .collect();
+    occupied.sort_by_key(|r| r.start);
+
+    // Merge overlapping and touching clips into continuous occupied spans
+    let mut spans: Vec<TimeRange> = Vec::new();
+    for range in occupied {
+        if let Some(last) = spans.last_mut()
+            && let Some(union) = last.union(range)
+        {
+            *last = union;
+        } else {
+            spans.push(range);
+        }
+    }
+
+    let mut cursor = Timecode::from_seconds(0.0);
+    let mut gaps = Vec::new();
+    for span in spans {
+        let gap = TimeRange::from_bounds(cursor, span.start);
+        if !gap.is_empty() {
+            gaps.push(gap);
+        }
+        cursor = span.end();
+    }
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::MediaId;
+
+    fn clip(position: f64, duration: f64) -> Clip {
+        Clip::new(
+            "clip".to_string(),
+            MediaId::new(),
+            Timecode::from_seconds(position),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(duration),
+        )
+    }
+
+    #[test]
+    fn test_gaps_include_leading_gap() {
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(clip(1.0, 2.0));
+        track.add_clip(clip(3.0, 1.0));
+        track.add_clip(clip(6.0, 2.0));
+
+        // Touching clips leave no gap between them
+        assert_eq!(
+            track.gaps(),
+            vec![
+                TimeRange::new(Timecode::from_seconds(0.0), Duration::from_secs(1)),
+                TimeRange::new(Timecode::from_seconds(4.0), Duration::from_secs(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_close_gap() {
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(clip(0.0, 2.0));
+        track.add_clip(clip(5.0, 2.0));
+
+        let overlapping = TimeRange::new(Timecode::from_seconds(1.0), Duration::from_secs(2));
+        assert!(track.close_gap(overlapping).is_err());
+
+        // Part of a gap may be closed
+        let part = TimeRange::new(Timecode::from_seconds(3.0), Duration::from_secs(1));
+        track.close_gap(part).unwrap();
+        assert_eq!(
+            track.clips()[1].timeline_position,
+            Timecode::from_seconds(4.0)
+        );
+        assert_eq!(track.gaps().len(), 1);
+    }
+
+    #[test]
+    fn test_track_close_all_gaps() {
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(clip(1.0, 2.0));
+        track.add_clip(clip(5.0, 2.0));
+        track.close_all_gaps();
+
+        assert!(track.gaps().is_empty());
+        assert_eq!(track.clips()[1].timeline_end(), Timecode::from_seconds(4.0));
+    }
 }
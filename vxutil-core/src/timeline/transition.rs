@@ -0,0 +1,325 @@
+//! Transition - a blend between two adjacent clips at an edit point
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_2;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{Clip, ClipId, TrackType};
+use crate::media::MediaLibrary;
+use crate::types::{TimeRange, Timecode};
+use crate::{Result, VxError};
+
+/// Unique identifier for a transition
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TransitionId(Uuid);
+
+impl TransitionId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for TransitionId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Direction for wipe and push transitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransitionDirection {
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
+}
+
+/// Gain curve for audio crossfades
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CrossfadeCurve {
+    Linear,
+    /// Keeps perceived loudness constant across the fade
+    #[default]
+    EqualPower,
+}
+
+/// Kind of transition
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TransitionKind {
+    CrossDissolve,
+    DipToBlack,
+    DipToWhite,
+    Wipe(TransitionDirection),
+    Push(TransitionDirection),
+    AudioCrossfade(CrossfadeCurve),
+}
+
+impl TransitionKind {
+    pub fn name(&self) -> &str {
+        match self {
+            TransitionKind::CrossDissolve => "Cross Dissolve",
+            TransitionKind::DipToBlack => "Dip to Black",
+            TransitionKind::DipToWhite => "Dip to White",
+            TransitionKind::Wipe(_) => "Wipe",
+            TransitionKind::Push(_) => "Push",
+            TransitionKind::AudioCrossfade(_) => "Audio Crossfade",
+        }
+    }
+
+    /// Track type this transition can be placed on
+    pub fn track_type(&self) -> TrackType {
+        match self {
+            TransitionKind::AudioCrossfade(_) => TrackType::Audio,
+            _ => TrackType::Video,
+        }
+    }
+
+    /// Mix weights (outgoing, incoming) at the given progress (0.0 to 1.0)
+    pub fn gains(&self, progress: f64) -> (f64, f64) {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            TransitionKind::AudioCrossfade(CrossfadeCurve::EqualPower) => {
+                let angle = progress * FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+            _ => (1.0 - progress, progress),
+        }
+    }
+}
+
+/// Where the transition sits relative to the edit point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TransitionAlignment {
+    /// Centred on the cut, uses handles from both clips
+    #[default]
+    Center,
+    /// Starts at the cut, uses the outgoing clip's tail handle
+    Start,
+    /// Ends at the cut, uses the incoming clip's head handle
+    End,
+}
+
+/// A transition attached to the edit point between two adjacent clips
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transition {
+    pub id: TransitionId,
+    pub kind: TransitionKind,
+    pub duration: Duration,
+    pub alignment: TransitionAlignment,
+
+    /// Clip ending at the edit point
+    pub outgoing: ClipId,
+
+    /// Clip starting at the edit point
+    pub incoming: ClipId,
+}
+
+impl Transition {
+    pub fn new(
+        kind: TransitionKind,
+        duration: Duration,
+        outgoing: ClipId,
+        incoming: ClipId,
+    ) -> Self {
+        Self {
+            id: TransitionId::new(),
+            kind,
+            duration,
+            alignment: TransitionAlignment::default(),
+            outgoing,
+            incoming,
+        }
+    }
+
+    /// Timeline duration needed beyond each side of the cut (before, after)
+    pub fn extents(&self) -> (Duration, Duration) {
+        match self.alignment {
+            TransitionAlignment::Center => {
+                let before = self.duration / 2;
+                (before, self.duration - before)
+            }
+            TransitionAlignment::Start => (Duration::ZERO, self.duration),
+            TransitionAlignment::End => (self.duration, Duration::ZERO),
+        }
+    }
+
+    /// Range covered on the timeline for an edit point at `cut`
+    pub fn range(&self, cut: Timecode) -> TimeRange {
+        let (before, _) = self.extents();
+        let start = cut.as_duration().saturating_sub(before);
+        TimeRange::new(Timecode(start), self.duration)
+    }
+
+    /// Progress (0.0 to 1.0) at the given timeline time, None outside the transition
+    pub fn progress_at(&self, cut: Timecode, time: Timecode) -> Option<f64> {
+        let range = self.range(cut);
+        if !range.contains(time) {
+            return None;
+        }
+        let elapsed = time.as_duration() - range.start.as_duration();
+        Some(elapsed.as_secs_f64() / self.duration.as_secs_f64())
+    }
+
+    /// Check that both clips are adjacent and have enough source media for this transition
+    pub fn validate(&self, outgoing: &Clip, incoming: &Clip, library: &MediaLibrary) -> Result<()> {
+        if self.duration.is_zero() {
+            return Err(VxError::InvalidParameter(
+                "Transition duration must be greater than zero".to_string(),
+            ));
+        }
+
+        if outgoing.timeline_end() != incoming.timeline_position {
+            return Err(VxError::Timeline(format!(
+                "Clips '{}' and '{}' are not adjacent",
+                outgoing.name, incoming.name
+            )));
+        }
+
+        let (before, after) = self.extents();
+        if before > outgoing.timeline_duration() || after > incoming.timeline_duration() {
+            return Err(VxError::Timeline(format!(
+                "Transition is longer than clips '{}' and '{}'",
+                outgoing.name, incoming.name
+            )));
+        }
+
+        // The outgoing clip keeps playing past its end for `after`, the incoming
+        // clip starts playing before its start for `before`. Reversed clips run
+        // through the opposite side of their source range.
+        let tail_needed = after.as_secs_f64() * outgoing.speed().abs();
+        if outgoing.is_reversed() {
+            check_head(outgoing, tail_needed)?;
+        } else {
+            check_tail(outgoing, tail_needed, library)?;
+        }
+
+        let head_needed = before.as_secs_f64() * incoming.speed().abs();
+        if incoming.is_reversed() {
+            check_tail(incoming, head_needed, library)?;
+        } else {
+            check_head(incoming, head_needed)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that a clip has `needed` seconds of source media after its out point.
+/// Generators and clips holding one frame (stills) have unlimited handles.
+fn check_tail(clip: &Clip, needed: f64, library: &MediaLibrary) -> Result<()> {
+    if clip.is_freeze_frame() {
+        return Ok(());
+    }
+    if let Some(media_duration) = clip
+        .media_id()
+        .and_then(|id| library.get_item(id))
+        .and_then(|item| item.duration_seconds())
+    {
+        let available = media_duration - clip.source_out.as_seconds();
+        if needed > available {
+            return Err(VxError::Timeline(format!(
+                "Insufficient handle on '{}': needs {:.3}s after out point, has {:.3}s",
+                clip.name,
+                needed,
+                available.max(0.0)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a clip has `needed` seconds of source media before its in point
+fn check_head(clip: &Clip, needed: f64) -> Result<()> {
+    let available = clip.source_in.as_seconds();
+    if clip.media_id().is_some() && !clip.is_freeze_frame() && needed > available {
+        return Err(VxError::Timeline(format!(
+            "Insufficient handle on '{}': needs {:.3}s before in point, has {:.3}s",
+            clip.name, needed, available
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::{MediaItem, MediaMetadata, MediaType};
+    use std::path::PathBuf;
+
+    fn setup() -> (MediaLibrary, Clip, Clip) {
+        let mut library = MediaLibrary::new();
+        let mut item = MediaItem::new(PathBuf::from("video.mp4"), MediaType::Video);
+        item.metadata = MediaMetadata {
+            duration: Some(20.0),
+            ..Default::default()
+        };
+        let media_id = library.add_item(item);
+
+        // Source 5-10s at 0-5s, then source 2-8s at 5-11s
+        let outgoing = Clip::new(
+            "A".to_string(),
+            media_id.clone(),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(5.0),
+            Timecode::from_seconds(10.0),
+        );
+        let incoming = Clip::new(
+            "B".to_string(),
+            media_id,
+            Timecode::from_seconds(5.0),
+            Timecode::from_seconds(2.0),
+            Timecode::from_seconds(8.0),
+        );
+        (library, outgoing, incoming)
+    }
+
+    #[test]
+    fn test_transition_range_alignment() {
+        let mut transition = Transition::new(
+            TransitionKind::CrossDissolve,
+            Duration::from_secs(2),
+            ClipId::new(),
+            ClipId::new(),
+        );
+        let cut = Timecode::from_seconds(5.0);
+
+        assert_eq!(transition.range(cut).start, Timecode::from_seconds(4.0));
+
+        transition.alignment = TransitionAlignment::Start;
+        assert_eq!(transition.range(cut).start, cut);
+
+        transition.alignment = TransitionAlignment::End;
+        assert_eq!(transition.range(cut).end(), cut);
+    }
+
+    #[test]
+    fn test_transition_handles() {
+        let (library, outgoing, incoming) = setup();
+
+        let mut transition = Transition::new(
+            TransitionKind::CrossDissolve,
+            Duration::from_secs(2),
+            outgoing.id.clone(),
+            incoming.id.clone(),
+        );
+        assert!(transition.validate(&outgoing, &incoming, &library).is_ok());
+
+        // Incoming clip only has 2 seconds before its in point
+        transition.duration = Duration::from_secs(3);
+        transition.alignment = TransitionAlignment::End;
+        assert!(transition.validate(&outgoing, &incoming, &library).is_err());
+
+        // Outgoing clip has 10 seconds after its out point
+        transition.alignment = TransitionAlignment::Start;
+        assert!(transition.validate(&outgoing, &incoming, &library).is_ok());
+    }
+
+    #[test]
+    fn test_equal_power_crossfade() {
+        let kind = TransitionKind::AudioCrossfade(CrossfadeCurve::EqualPower);
+        let (out_gain, in_gain) = kind.gains(0.5);
+        assert!((out_gain * out_gain + in_gain * in_gain - 1.0).abs() < 1e-9);
+        assert_eq!(kind.gains(0.0), (1.0, 0.0));
+    }
+}
@@ -42,6 +42,11 @@ impl FrameRate {
         }
     }
 
+    /// Whether both parts are non-zero, so the rate can be used for timing
+    pub fn is_valid(&self) -> bool {
+        self.numerator != 0 && self.denominator != 0
+    }
+
     pub fn as_f64(&self) -> f64 {
         self.numerator as f64 / self.denominator as f64
     }
@@ -0,0 +1,35 @@
+//! Label colors for markers and other tagged items
+
+use serde::{Deserialize, Serialize};
+
+use super::Color;
+
+/// Label color shown in the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum LabelColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    #[default]
+    Blue,
+    Purple,
+    Pink,
+}
+
+impl LabelColor {
+    /// Display color for this label
+    pub fn color(&self) -> Color {
+        match self {
+            LabelColor::Red => Color::from_rgba8(229, 57, 53, 255),
+            LabelColor::Orange => Color::from_rgba8(251, 140, 0, 255),
+            LabelColor::Yellow => Color::from_rgba8(253, 216, 53, 255),
+            LabelColor::Green => Color::from_rgba8(67, 160, 71, 255),
+            LabelColor::Cyan => Color::from_rgba8(0, 172, 193, 255),
+            LabelColor::Blue => Color::from_rgba8(30, 136, 229, 255),
+            LabelColor::Purple => Color::from_rgba8(142, 36, 170, 255),
+            LabelColor::Pink => Color::from_rgba8(216, 27, 96, 255),
+        }
+    }
+}
@@ -1,11 +1,15 @@
 //! Common types used throughout VxUtil
 
+mod color;
 mod framerate;
+mod label_color;
 mod resolution;
 mod timecode;
 mod timerange;
 
+pub use color::Color;
 pub use framerate::FrameRate;
+pub use label_color::LabelColor;
 pub use resolution::Resolution;
 pub use timecode::{FrameNumber, Timecode};
 pub use timerange::TimeRange;
@@ -5,8 +5,8 @@ use std::time::Duration;
 
 use super::Timecode;
 
-/// Time range in the timeline
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Half-open time range `[start, end)` in the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TimeRange {
     pub start: Timecode,
     pub duration: Duration,
@@ -17,11 +17,182 @@ impl TimeRange {
         Self { start, duration }
     }
 
+    /// Range from `start` to `end`. Empty (at `start`) if `end` is before `start`.
+    pub fn from_bounds(start: Timecode, end: Timecode) -> Self {
+        Self::new(start, end.as_duration().saturating_sub(start.as_duration()))
+    }
+
     pub fn end(&self) -> Timecode {
         Timecode(self.start.0 + self.duration)
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.duration.is_zero()
+    }
+
     pub fn contains(&self, time: Timecode) -> bool {
         time >= self.start && time < self.end()
     }
+
+    /// Check if `other` lies entirely within this range
+    pub fn contains_range(&self, other: TimeRange) -> bool {
+        other.start >= self.start && other.end() <= self.end()
+    }
+
+    /// Check if the ranges share any time. Touching ranges do not intersect.
+    pub fn intersects(&self, other: TimeRange) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.start < other.end()
+            && other.start < self.end()
+    }
+
+    /// Overlapping part of both ranges, None if they do not intersect
+    pub fn intersection(&self, other: TimeRange) -> Option<TimeRange> {
+        if !self.intersects(other) {
+            return None;
+        }
+        Some(Self::from_bounds(
+            self.start.max(other.start),
+            self.end().min(other.end()),
+        ))
+    }
+
+    /// Combined range if the ranges intersect or touch, None if there is a hole between them
+    pub fn union(&self, other: TimeRange) -> Option<TimeRange> {
+        if self.is_empty() {
+            return Some(other);
+        }
+        if other.is_empty() {
+            return Some(*self);
+        }
+        if self.start > other.end() || other.start > self.end() {
+            return None;
+        }
+        Some(Self::from_bounds(
+            self.start.min(other.start),
+            self.end().max(other.end()),
+        ))
+    }
+
+    /// Parts of this range not covered by `other` (zero, one or two ranges)
+    pub fn subtract(&self, other: TimeRange) -> Vec<TimeRange> {
+        if !self.intersects(other) {
+            return if self.is_empty() {
+                Vec::new()
+            } else {
+                vec![*self]
+            };
+        }
+
+        [
+            Self::from_bounds(self.start, other.start),
+            Self::from_bounds(other.end(), self.end()),
+        ]
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .collect()
+    }
+
+    /// Clamp a time into this range (`end` is allowed)
+    pub fn clamp(&self, time: Timecode) -> Timecode {
+        time.clamp(self.start, self.end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn range(start: u64, end: u64) -> TimeRange {
+        TimeRange::from_bounds(
+            Timecode(Duration::from_millis(start)),
+            Timecode(Duration::from_millis(end)),
+        )
+    }
+
+    fn arb_range() -> impl Strategy<Value = TimeRange> {
+        (0u64..1000, 0u64..200).prop_map(|(start, len)| range(start, start + len))
+    }
+
+    #[test]
+    fn test_touching_ranges() {
+        let a = range(0, 10);
+        let b = range(10, 20);
+
+        assert!(!a.intersects(b));
+        assert_eq!(a.intersection(b), None);
+        assert_eq!(a.union(b), Some(range(0, 20)));
+        assert_eq!(a.subtract(b), vec![a]);
+    }
+
+    #[test]
+    fn test_subtract_middle() {
+        assert_eq!(
+            range(0, 30).subtract(range(10, 20)),
+            vec![range(0, 10), range(20, 30)]
+        );
+        assert!(range(10, 20).subtract(range(0, 30)).is_empty());
+    }
+
+    #[test]
+    fn test_clamp() {
+        let r = range(10, 20);
+        assert_eq!(r.clamp(Timecode(Duration::from_millis(5))), r.start);
+        assert_eq!(r.clamp(Timecode(Duration::from_millis(25))), r.end());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_intersects_matches_contains(a in arb_range(), b in arb_range(), t in 0u64..1200) {
+            let time = Timecode(Duration::from_millis(t));
+            if a.contains(time) && b.contains(time) {
+                prop_assert!(a.intersects(b));
+                prop_assert!(a.intersection(b).unwrap().contains(time));
+            }
+        }
+
+        #[test]
+        fn prop_intersection_is_symmetric(a in arb_range(), b in arb_range()) {
+            prop_assert_eq!(a.intersects(b), b.intersects(a));
+            prop_assert_eq!(a.intersection(b), b.intersection(a));
+        }
+
+        #[test]
+        fn prop_intersection_within_both(a in arb_range(), b in arb_range()) {
+            if let Some(i) = a.intersection(b) {
+                prop_assert!(!i.is_empty());
+                prop_assert!(a.contains_range(i) && b.contains_range(i));
+            }
+        }
+
+        #[test]
+        fn prop_subtract_partitions(a in arb_range(), b in arb_range(), t in 0u64..1200) {
+            let time = Timecode(Duration::from_millis(t));
+            let pieces = a.subtract(b);
+            let in_pieces = pieces.iter().filter(|p| p.contains(time)).count();
+
+            // Every point of `a` is either in `b` or in exactly one piece
+            if a.contains(time) {
+                prop_assert_eq!(in_pieces, if b.contains(time) { 0 } else { 1 });
+            } else {
+                prop_assert_eq!(in_pieces, 0);
+            }
+            prop_assert!(pieces.iter().all(|p| !p.intersects(b) && a.contains_range(*p)));
+        }
+
+        #[test]
+        fn prop_union_covers_both(a in arb_range(), b in arb_range(), t in 0u64..1200) {
+            let time = Timecode(Duration::from_millis(t));
+            if let Some(u) = a.union(b) {
+                if a.contains(time) || b.contains(time) {
+                    prop_assert!(u.contains(time));
+                }
+                prop_assert!(u.duration <= a.duration + b.duration);
+            } else {
+                prop_assert!(!a.intersects(b));
+            }
+        }
+    }
 }
@@ -0,0 +1,265 @@
+//! On-disk caches of data derived from media content, keyed by that content
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use vxutil_core::media::{Fingerprint, MediaItem, MediaLibrary};
+
+use crate::Result;
+
+/// Identifies the current content of a media item on disk: its fingerprint plus
+/// the modification times of the file, or of every frame of an image sequence.
+/// Edits that keep the size and both ends of a file still change the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentKey {
+    /// Fingerprint of the media file, or of the first frame of a sequence
+    pub fingerprint: Fingerprint,
+
+    /// Hash of the modification times, and for sequences the frame sizes
+    stamp: u64,
+}
+
+impl ContentKey {
+    pub fn compute(item: &MediaItem) -> Result<Self> {
+        let fingerprint = Fingerprint::compute(&item.path)?;
+        let stamp = match &item.image_sequence {
+            Some(sequence) => sequence
+                .frame_range()
+                .filter(|number| sequence.missing.binary_search(number).is_err())
+                .fold(OFFSET_BASIS, |hash, number| {
+                    let frame = item.path.with_file_name(sequence.file_name(number));
+                    // A frame deleted since detection still changes the key
+                    let (size, modified) = file_stamp(&frame).unwrap_or_default();
+                    mix(mix(mix(hash, number), size), modified)
+                }),
+            None => {
+                let (_, modified) = file_stamp(&item.path)?;
+                mix(OFFSET_BASIS, modified)
+            }
+        };
+        Ok(Self { fingerprint, stamp })
+    }
+
+    /// Name of the cache entries for this content
+    pub fn name(&self) -> String {
+        let fingerprint = &self.fingerprint;
+        format!(
+            "{:x}-{:016x}-{:016x}-{:016x}",
+            fingerprint.size, fingerprint.head, fingerprint.tail, self.stamp
+        )
+    }
+}
+
+/// Extension of files being written by [`write_atomic`]
+const PARTIAL_EXTENSION: &str = "part";
+
+/// Entries stored below a cache directory, one per [`ContentKey`]. An entry is a
+/// file with the cache's extension, or a folder of files if it has none.
+#[derive(Debug, Clone)]
+pub struct ContentCache {
+    dir: PathBuf,
+    extension: Option<&'static str>,
+}
+
+impl ContentCache {
+    pub fn new(dir: PathBuf, extension: Option<&'static str>) -> Self {
+        Self { dir, extension }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File or folder holding the entry of some content
+    pub fn entry_path(&self, key: &ContentKey) -> PathBuf {
+        let path = self.dir.join(key.name());
+        match self.extension {
+            Some(extension) => path.with_extension(extension),
+            None => path,
+        }
+    }
+
+    /// Delete the entry of some content
+    pub fn remove(&self, key: &ContentKey) -> Result<()> {
+        let path = self.entry_path(key);
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Delete the entries of content no item accepted by `uses` has any more, and
+    /// partial files left by interrupted writes. Each item's file is read again to
+    /// find its current key; offline items keep nothing. Returns the number of
+    /// entries deleted, not counting partial files.
+    pub fn prune(
+        &self,
+        library: &MediaLibrary,
+        uses: impl Fn(&MediaItem) -> bool,
+    ) -> Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        // Duplicates share a key, so an entry stays while any of them remains
+        let used: HashSet<PathBuf> = library
+            .items()
+            .filter(|item| item.exists() && uses(item))
+            .filter_map(|item| ContentKey::compute(item).ok())
+            .map(|key| self.entry_path(&key))
+            .collect();
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if is_partial(&path) {
+                fs::remove_file(path)?;
+                continue;
+            }
+            let is_entry = match self.extension {
+                Some(extension) => {
+                    path.is_file() && path.extension().is_some_and(|e| e == extension)
+                }
+                None => path.is_dir(),
+            };
+            if !is_entry {
+                continue;
+            }
+            if !used.contains(&path) {
+                self.remove_entry(&path)?;
+                removed += 1;
+            } else if path.is_dir() {
+                remove_partials(&path)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn remove_entry(&self, path: &Path) -> Result<()> {
+        match self.extension {
+            Some(_) => fs::remove_file(path)?,
+            None => fs::remove_dir_all(path)?,
+        }
+        Ok(())
+    }
+}
+
+/// Write a cache file with `write`, which is given a path beside the final one;
+/// the file is renamed into place once complete so readers never see part of it
+pub fn write_atomic(path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".");
+    partial.push(PARTIAL_EXTENSION);
+    let partial = PathBuf::from(partial);
+    if let Err(e) = write(&partial) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(partial, path)?;
+    Ok(())
+}
+
+fn is_partial(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|e| e == PARTIAL_EXTENSION)
+}
+
+/// Delete the partial files directly inside a folder entry
+fn remove_partials(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_partial(&path) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// One FNV-1a step over the bytes of `value`
+fn mix(hash: u64, value: u64) -> u64 {
+    value.to_le_bytes().iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Size and modification time in nanoseconds of a file
+fn file_stamp(path: &Path) -> Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    Ok((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+    use vxutil_core::FrameRate;
+    use vxutil_core::media::{MediaLibrary, MediaType};
+
+    /// Rewrite a file and move its modification time on, as an editor saving it would
+    fn rewrite(path: &Path, contents: &[u8]) {
+        fs::write(path, contents).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+    }
+
+    fn temp_dir() -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("vxutil-content-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_edit_inside_file_changes_key() {
+        let root = temp_dir();
+        let path = root.join("render.mov");
+        let mut contents = vec![0u8; 512 * 1024];
+        fs::write(&path, &contents).unwrap();
+        let item = MediaItem::new(path.clone(), MediaType::Video);
+        let before = ContentKey::compute(&item).unwrap();
+        assert_eq!(ContentKey::compute(&item).unwrap(), before);
+
+        // Same size and ends, so only the modification time tells them apart
+        contents[256 * 1024] = 1;
+        rewrite(&path, &contents);
+        let after = ContentKey::compute(&item).unwrap();
+        assert_eq!(after.fingerprint, before.fingerprint);
+        assert_ne!(after.name(), before.name());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_later_sequence_frame_changes_key() {
+        let root = temp_dir();
+        for number in 1..=3 {
+            fs::write(root.join(format!("shot_{number:04}.png")), b"frame").unwrap();
+        }
+        let mut library = MediaLibrary::new();
+        let id = library
+            .import_image_sequence(&root.join("shot_0001.png"), FrameRate::FPS_24)
+            .unwrap();
+        let item = library.get_item(&id).unwrap();
+        let before = ContentKey::compute(item).unwrap();
+
+        rewrite(&root.join("shot_0003.png"), b"new frame");
+        let after = ContentKey::compute(item).unwrap();
+        assert_eq!(after.fingerprint, before.fingerprint);
+        assert_ne!(after, before);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
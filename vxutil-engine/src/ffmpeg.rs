@@ -1,8 +1,18 @@
 //! FFmpeg wrapper for video/audio processing
 
+use crate::still::decode_image;
+use crate::waveform::{Waveform, WaveformBuilder};
 use crate::{Result, EngineError};
+use ffmpeg_next::software::{resampling, scaling};
+use ffmpeg_next::{
+    ChannelLayout, Packet, Rational, Rescale, codec, decoder, encoder, format, frame, media,
+    picture,
+};
+use image::RgbaImage;
 use std::path::Path;
-use vxutil_core::{FrameRate, Resolution};
+use vxutil_core::media::{MediaItem, MediaType};
+use vxutil_core::timeline::Chapter;
+use vxutil_core::{FrameRate, Resolution, TimeRange, Timecode};
 
 pub struct VideoMetadata {
     pub duration_seconds: f64,
@@ -19,6 +29,478 @@ pub fn get_video_metadata(path: &Path) -> Result<VideoMetadata> {
     Err(EngineError::FFmpeg("Not yet implemented".to_string()))
 }
 
+/// Decode the frame of a media item shown at a source time: a video frame, a
+/// frame of an image sequence or the image itself. Audio has no frames.
+pub fn media_frame(item: &MediaItem, time: Timecode) -> Result<RgbaImage> {
+    match item.media_type {
+        _ if item.image_sequence.is_some() => decode_still(&item.frame_path(time)),
+        MediaType::Image => decode_still(&item.path),
+        MediaType::Video => decode_video_frame(&item.path, time),
+        MediaType::Audio => Err(EngineError::Decode(format!(
+            "{} has no pictures",
+            item.name
+        ))),
+    }
+}
+
+/// Decode a still image or image sequence frame. Formats the `image` crate cannot
+/// read, such as DPX, are opened by ffmpeg, which reads single image files with
+/// its image2 demuxer.
+pub fn decode_still(path: &Path) -> Result<RgbaImage> {
+    match image::ImageFormat::from_path(path) {
+        Ok(format) if format.reading_enabled() => decode_image(path),
+        _ => decode_video_frame(path, Timecode::from_seconds(0.0)),
+    }
+}
+
+/// Decode the first video frame at or after `time`, or the last frame if the
+/// file ends before it
+pub fn decode_video_frame(path: &Path, time: Timecode) -> Result<RgbaImage> {
+    ffmpeg_next::init().map_err(ffmpeg_error)?;
+    let mut ictx = format::input(path).map_err(ffmpeg_error)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Video)
+        .ok_or_else(|| EngineError::Decode(format!("{} has no video", path.display())))?;
+    let (index, time_base) = (stream.index(), stream.time_base());
+    let mut decoder = codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|context| context.decoder().video())
+        .map_err(ffmpeg_error)?;
+
+    // Image demuxers may not seek, and the first frame needs no seek anyway
+    let target = time.as_seconds();
+    if target > 0.0 {
+        let seek_target = (target * 1_000_000.0) as i64;
+        ictx.seek(seek_target, ..seek_target)
+            .map_err(ffmpeg_error)?;
+    }
+
+    let mut decoded = frame::Video::empty();
+    let mut latest = None;
+    // Timestamps are rounded to the time base, so allow for a little slack
+    let reached = |frame: &frame::Video| {
+        frame
+            .timestamp()
+            .is_none_or(|timestamp| seconds(timestamp, time_base) >= target - 1e-3)
+    };
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| EngineError::Decode(e.to_string()))?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            if reached(&decoded) {
+                return to_rgba_image(&decoded);
+            }
+            latest = Some(decoded.clone());
+        }
+    }
+
+    decoder
+        .send_eof()
+        .map_err(|e| EngineError::Decode(e.to_string()))?;
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        if reached(&decoded) {
+            return to_rgba_image(&decoded);
+        }
+        latest = Some(decoded.clone());
+    }
+    match latest {
+        Some(frame) => to_rgba_image(&frame),
+        None => Err(EngineError::Decode(format!(
+            "No frame decoded from {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Convert a decoded frame to a tightly packed RGBA image
+fn to_rgba_image(decoded: &frame::Video) -> Result<RgbaImage> {
+    let (width, height) = (decoded.width(), decoded.height());
+    let mut scaler = scaling::Context::get(
+        decoded.format(),
+        width,
+        height,
+        format::Pixel::RGBA,
+        width,
+        height,
+        scaling::Flags::BILINEAR,
+    )
+    .map_err(ffmpeg_error)?;
+    let mut rgba = frame::Video::empty();
+    scaler.run(decoded, &mut rgba).map_err(ffmpeg_error)?;
+
+    // Rows may be padded past the visible width
+    let stride = rgba.stride(0);
+    let row_bytes = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in rgba.data(0).chunks(stride).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| EngineError::Decode("Converted frame has the wrong size".to_string()))
+}
+
+/// Decode the best audio stream of a file into waveform peaks
+pub fn build_waveform(path: &Path) -> Result<Waveform> {
+    ffmpeg_next::init().map_err(ffmpeg_error)?;
+    let mut ictx = format::input(path).map_err(ffmpeg_error)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Audio)
+        .ok_or_else(|| EngineError::Decode(format!("{} has no audio", path.display())))?;
+    let index = stream.index();
+    let mut decoder = codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|context| context.decoder().audio())
+        .map_err(ffmpeg_error)?;
+
+    // Some files leave the layout unset, so fall back to the default for the count
+    let (rate, channels) = (decoder.rate(), decoder.channels());
+    let layout = match decoder.channel_layout() {
+        layout if layout.channels() > 0 => layout,
+        _ => ChannelLayout::default(channels as i32),
+    };
+    let mut resampler = resampling::Context::get(
+        decoder.format(),
+        layout,
+        rate,
+        format::Sample::F32(format::sample::Type::Packed),
+        layout,
+        rate,
+    )
+    .map_err(ffmpeg_error)?;
+
+    let mut builder = WaveformBuilder::new(rate, channels);
+    let mut decoded = frame::Audio::empty();
+    let mut receive = |decoder: &mut decoder::Audio, builder: &mut WaveformBuilder| -> Result<()> {
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let mut converted = frame::Audio::empty();
+            resampler
+                .run(&decoded, &mut converted)
+                .map_err(ffmpeg_error)?;
+            push_samples(builder, &converted, channels);
+        }
+        Ok(())
+    };
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| EngineError::Decode(e.to_string()))?;
+        receive(&mut decoder, &mut builder)?;
+    }
+    decoder
+        .send_eof()
+        .map_err(|e| EngineError::Decode(e.to_string()))?;
+    receive(&mut decoder, &mut builder)?;
+    Ok(builder.finish())
+}
+
+/// Add the samples of a packed f32 frame to a waveform
+fn push_samples(builder: &mut WaveformBuilder, converted: &frame::Audio, channels: u16) {
+    // The plane may be padded past the last sample
+    let len = converted.samples() * channels as usize * 4;
+    let samples: Vec<f32> = converted.data(0)[..len]
+        .chunks_exact(4)
+        .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    builder.push(&samples);
+}
+
+/// Add chapter points to an output container (MP4/MKV).
+/// Must be called before the container header is written.
+pub fn write_chapters(
+    output: &mut ffmpeg_next::format::context::Output,
+    chapters: &[Chapter],
+) -> Result<()> {
+    // Chapter times are written in milliseconds
+    let to_millis = |time: Timecode| time.as_duration().as_millis() as i64;
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        output
+            .add_chapter(
+                index as i64,
+                (1, 1000),
+                to_millis(chapter.start),
+                to_millis(chapter.end),
+                &chapter.title,
+            )
+            .map_err(|e| EngineError::FFmpeg(format!("Failed to add chapter: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// How [`trim_media`] cuts the source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimMode {
+    /// Copy packets unchanged. Fast and lossless, but the new file starts at the
+    /// keyframe before the range.
+    StreamCopy,
+    /// Re-encode video with the source codec for a frame-accurate cut.
+    /// Audio is still copied packet by packet.
+    Transcode,
+}
+
+/// Write the part of `source` covering `range` to `destination`, keeping all video
+/// and audio streams. Returns the source time the new file starts at, as expected
+/// by `Project::collect`.
+pub fn trim_media(
+    source: &Path,
+    destination: &Path,
+    range: TimeRange,
+    mode: TrimMode,
+) -> Result<Timecode> {
+    ffmpeg_next::init().map_err(ffmpeg_error)?;
+    let mut ictx = format::input(source).map_err(ffmpeg_error)?;
+    let mut octx = format::output(destination).map_err(ffmpeg_error)?;
+
+    // Stream copies start at whatever keyframe the primary stream begins with
+    let primary = ictx
+        .streams()
+        .best(media::Type::Video)
+        .or_else(|| ictx.streams().best(media::Type::Audio))
+        .map(|stream| stream.index());
+    let mut outputs = Vec::new();
+    for ist in ictx.streams() {
+        let medium = ist.parameters().medium();
+        if medium != media::Type::Video && medium != media::Type::Audio {
+            outputs.push(None);
+            continue;
+        }
+        let index = octx.nb_streams() as usize;
+        let transcoder = if mode == TrimMode::Transcode && medium == media::Type::Video {
+            Some(VideoTranscoder::new(&ist, &mut octx)?)
+        } else {
+            let mut ost = octx
+                .add_stream(encoder::find(codec::Id::None))
+                .map_err(ffmpeg_error)?;
+            ost.set_parameters(ist.parameters());
+            // Codec tags from the source container may be invalid in the output one
+            unsafe {
+                (*ost.parameters().as_mut_ptr()).codec_tag = 0;
+            }
+            None
+        };
+        outputs.push(Some(TrimStream {
+            index,
+            input_time_base: ist.time_base(),
+            output_time_base: Rational(0, 1),
+            transcoder,
+            done: false,
+        }));
+    }
+
+    octx.set_metadata(ictx.metadata().to_owned());
+    octx.write_header().map_err(ffmpeg_error)?;
+    for output in outputs.iter_mut().flatten() {
+        if let Some(stream) = octx.stream(output.index) {
+            output.output_time_base = stream.time_base();
+        }
+    }
+
+    let start = range.start.as_seconds();
+    let end = range.end().as_seconds();
+    let seek_target = (start * 1_000_000.0) as i64;
+    ictx.seek(seek_target, ..seek_target)
+        .map_err(ffmpeg_error)?;
+
+    let mut origin = (mode == TrimMode::Transcode).then_some(start);
+    for (stream, mut packet) in ictx.packets() {
+        let Some(output) = outputs.get_mut(stream.index()).and_then(Option::as_mut) else {
+            continue;
+        };
+        if output.done {
+            continue;
+        }
+        let Some(time) = packet.pts().or(packet.dts()) else {
+            continue;
+        };
+        let time = seconds(time, output.input_time_base);
+        let decode_time = packet
+            .dts()
+            .map_or(time, |dts| seconds(dts, output.input_time_base));
+
+        if origin.is_none() && primary == Some(stream.index()) {
+            origin = Some(time);
+        }
+        let Some(origin) = origin else {
+            continue;
+        };
+        if decode_time >= end {
+            output.done = true;
+            if let Some(transcoder) = &mut output.transcoder {
+                transcoder.finish(&mut octx, output.index, origin, end)?;
+            }
+            if outputs.iter().flatten().all(|output| output.done) {
+                break;
+            }
+            continue;
+        }
+
+        match &mut output.transcoder {
+            Some(transcoder) => {
+                transcoder.decode(&packet)?;
+                transcoder.encode_frames(&mut octx, output.index, origin, end)?;
+            }
+            None => {
+                if time < origin {
+                    continue;
+                }
+                let shift = ((origin * 1_000_000.0) as i64)
+                    .rescale(ffmpeg_next::rescale::TIME_BASE, output.input_time_base);
+                packet.set_pts(packet.pts().map(|pts| pts - shift));
+                packet.set_dts(packet.dts().map(|dts| dts - shift));
+                packet.rescale_ts(output.input_time_base, output.output_time_base);
+                packet.set_position(-1);
+                packet.set_stream(output.index);
+                packet.write_interleaved(&mut octx).map_err(ffmpeg_error)?;
+            }
+        }
+    }
+
+    // Streams that ran out before reaching the end of the range
+    let origin = origin.unwrap_or(start);
+    for output in outputs.iter_mut().flatten() {
+        if !output.done
+            && let Some(transcoder) = &mut output.transcoder
+        {
+            transcoder.finish(&mut octx, output.index, origin, end)?;
+        }
+    }
+    octx.write_trailer().map_err(ffmpeg_error)?;
+
+    Ok(Timecode::from_seconds(origin))
+}
+
+/// Per-stream state while trimming
+struct TrimStream {
+    index: usize,
+    input_time_base: Rational,
+    output_time_base: Rational,
+    transcoder: Option<VideoTranscoder>,
+    done: bool,
+}
+
+/// Decoder and encoder pair re-encoding one video stream with its own codec
+struct VideoTranscoder {
+    decoder: decoder::Video,
+    encoder: encoder::Video,
+    time_base: Rational,
+}
+
+impl VideoTranscoder {
+    fn new(ist: &format::stream::Stream, octx: &mut format::context::Output) -> Result<Self> {
+        let global_header = octx.format().flags().contains(format::Flags::GLOBAL_HEADER);
+        let decoder = codec::context::Context::from_parameters(ist.parameters())
+            .and_then(|context| context.decoder().video())
+            .map_err(ffmpeg_error)?;
+
+        let codec = encoder::find(ist.parameters().id())
+            .ok_or_else(|| EngineError::Encode("No encoder for the source codec".to_string()))?;
+        let mut ost = octx.add_stream(codec).map_err(ffmpeg_error)?;
+        let mut encoder = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()
+            .map_err(ffmpeg_error)?;
+        encoder.set_width(decoder.width());
+        encoder.set_height(decoder.height());
+        encoder.set_aspect_ratio(decoder.aspect_ratio());
+        encoder.set_format(decoder.format());
+        encoder.set_frame_rate(decoder.frame_rate());
+        encoder.set_time_base(ist.time_base());
+        if global_header {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = encoder.open().map_err(ffmpeg_error)?;
+        ost.set_parameters(&encoder);
+        Ok(Self {
+            decoder,
+            encoder,
+            time_base: ist.time_base(),
+        })
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<()> {
+        self.decoder
+            .send_packet(packet)
+            .map_err(|e| EngineError::Decode(e.to_string()))
+    }
+
+    /// Re-encode decoded frames inside `[origin, end)`, shifted to start at zero
+    fn encode_frames(
+        &mut self,
+        octx: &mut format::context::Output,
+        index: usize,
+        origin: f64,
+        end: f64,
+    ) -> Result<()> {
+        let shift = ((origin * 1_000_000.0) as i64)
+            .rescale(ffmpeg_next::rescale::TIME_BASE, self.time_base);
+        let mut frame = frame::Video::empty();
+        while self.decoder.receive_frame(&mut frame).is_ok() {
+            let Some(timestamp) = frame.timestamp() else {
+                continue;
+            };
+            let time = seconds(timestamp, self.time_base);
+            if time < origin || time >= end {
+                continue;
+            }
+            frame.set_pts(Some(timestamp - shift));
+            frame.set_kind(picture::Type::None);
+            self.encoder
+                .send_frame(&frame)
+                .map_err(|e| EngineError::Encode(e.to_string()))?;
+            self.write_packets(octx, index)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the decoder and encoder
+    fn finish(
+        &mut self,
+        octx: &mut format::context::Output,
+        index: usize,
+        origin: f64,
+        end: f64,
+    ) -> Result<()> {
+        self.decoder
+            .send_eof()
+            .map_err(|e| EngineError::Decode(e.to_string()))?;
+        self.encode_frames(octx, index, origin, end)?;
+        self.encoder
+            .send_eof()
+            .map_err(|e| EngineError::Encode(e.to_string()))?;
+        self.write_packets(octx, index)
+    }
+
+    fn write_packets(&mut self, octx: &mut format::context::Output, index: usize) -> Result<()> {
+        let output_time_base = octx
+            .stream(index)
+            .map_or(self.time_base, |stream| stream.time_base());
+        let mut packet = Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(index);
+            packet.rescale_ts(self.time_base, output_time_base);
+            packet.write_interleaved(octx).map_err(ffmpeg_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Timestamp in a stream time base to seconds
+fn seconds(timestamp: i64, time_base: Rational) -> f64 {
+    timestamp as f64 * f64::from(time_base)
+}
+
+fn ffmpeg_error(error: ffmpeg_next::Error) -> EngineError {
+    EngineError::FFmpeg(error.to_string())
+}
+
 pub struct VideoDecoder {
     // TODO: FFmpeg decoder state
 }
@@ -0,0 +1,55 @@
+//! Frame-by-frame decoding of image sequence media
+
+use image::RgbaImage;
+use std::path::{Path, PathBuf};
+use vxutil_core::media::MediaItem;
+use vxutil_core::{FrameRate, Timecode};
+
+use crate::{EngineError, Result};
+
+/// Decodes the frames of an image sequence item one file at a time. Missing
+/// frames show the previous frame.
+pub struct ImageSequenceReader {
+    item: MediaItem,
+
+    /// Decodes one frame file, e.g. [`crate::ffmpeg::decode_still`] which also
+    /// handles formats the `image` crate cannot read (DPX, ...)
+    decode: fn(&Path) -> Result<RgbaImage>,
+
+    /// Last decoded frame file, reused while a frame is held
+    last: Option<(PathBuf, RgbaImage)>,
+}
+
+impl ImageSequenceReader {
+    pub fn new(item: &MediaItem, decode: fn(&Path) -> Result<RgbaImage>) -> Result<Self> {
+        if item.image_sequence.is_none() {
+            return Err(EngineError::Decode(format!(
+                "{} is not an image sequence",
+                item.name
+            )));
+        }
+        Ok(Self {
+            item: item.clone(),
+            decode,
+            last: None,
+        })
+    }
+
+    pub fn frame_rate(&self) -> Option<FrameRate> {
+        self.item.image_sequence.as_ref().map(|s| s.frame_rate)
+    }
+
+    /// Decoded frame shown at a time from the sequence start
+    pub fn frame(&mut self, source_time: Timecode) -> Result<RgbaImage> {
+        let path = self.item.frame_path(source_time);
+        if let Some((last_path, image)) = &self.last
+            && *last_path == path
+        {
+            return Ok(image.clone());
+        }
+
+        let image = (self.decode)(&path)?;
+        self.last = Some((path, image.clone()));
+        Ok(image)
+    }
+}
@@ -7,10 +7,15 @@
 //! - GPU acceleration (WGPU)
 //! - Frame caching
 
+pub mod content_cache;
 pub mod error;
 pub mod ffmpeg;
+pub mod image_sequence;
 pub mod playback;
 pub mod rendering;
+pub mod still;
+pub mod thumbnails;
+pub mod waveform;
 pub mod cache;
 
 pub use error::{Result, EngineError};
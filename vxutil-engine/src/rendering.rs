@@ -1,3 +0,0 @@
-//! Rendering and compositing pipeline
-
-// TODO: Implement renderer, compositor, and export functionality
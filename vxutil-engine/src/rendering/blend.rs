@@ -0,0 +1,145 @@
+//! Pixel blending for compositing layers
+
+use image::RgbaImage;
+use vxutil_core::timeline::BlendMode;
+
+/// Separable blend function B(backdrop, source) on normalized channels
+fn blend_channel(mode: BlendMode, backdrop: f32, source: f32) -> f32 {
+    match mode {
+        BlendMode::Normal => source,
+        BlendMode::Multiply => backdrop * source,
+        BlendMode::Screen => backdrop + source - backdrop * source,
+        BlendMode::Overlay => {
+            if backdrop <= 0.5 {
+                2.0 * backdrop * source
+            } else {
+                1.0 - 2.0 * (1.0 - backdrop) * (1.0 - source)
+            }
+        }
+        BlendMode::Add => (backdrop + source).min(1.0),
+        BlendMode::Subtract => (backdrop - source).max(0.0),
+        BlendMode::Darken => backdrop.min(source),
+        BlendMode::Lighten => backdrop.max(source),
+    }
+}
+
+/// Composite `layer` over `canvas` with the given blend mode and opacity.
+/// Both images must have the same dimensions.
+pub(crate) fn blend_layer(
+    canvas: &mut RgbaImage,
+    layer: &RgbaImage,
+    mode: BlendMode,
+    opacity: f32,
+) {
+    let opacity = opacity.clamp(0.0, 1.0);
+    if opacity <= 0.0 {
+        return;
+    }
+
+    for (dst, src) in canvas.pixels_mut().zip(layer.pixels()) {
+        let src_alpha = src.0[3] as f32 / 255.0 * opacity;
+        if src_alpha <= 0.0 {
+            continue;
+        }
+        let dst_alpha = dst.0[3] as f32 / 255.0;
+        let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+
+        for channel in 0..3 {
+            let backdrop = dst.0[channel] as f32 / 255.0;
+            let source = src.0[channel] as f32 / 255.0;
+
+            // Where the backdrop is transparent the source shows unblended
+            let mixed =
+                (1.0 - dst_alpha) * source + dst_alpha * blend_channel(mode, backdrop, source);
+            let color = (mixed * src_alpha + backdrop * dst_alpha * (1.0 - src_alpha)) / out_alpha;
+            dst.0[channel] = (color.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        dst.0[3] = (out_alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+/// Copy of `layer` whose colors are already blended onto `backdrop` by `mode`,
+/// keeping the layer's alpha. Compositing the result with a second mode applies
+/// that mode on top of the first. Both images must have the same dimensions.
+pub(crate) fn pre_blend(backdrop: &RgbaImage, layer: &RgbaImage, mode: BlendMode) -> RgbaImage {
+    let mut out = layer.clone();
+    if mode == BlendMode::Normal {
+        return out;
+    }
+    for (dst, under) in out.pixels_mut().zip(backdrop.pixels()) {
+        let under_alpha = under.0[3] as f32 / 255.0;
+        for channel in 0..3 {
+            let backdrop = under.0[channel] as f32 / 255.0;
+            let source = dst.0[channel] as f32 / 255.0;
+            let mixed =
+                (1.0 - under_alpha) * source + under_alpha * blend_channel(mode, backdrop, source);
+            dst.0[channel] = (mixed.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+    out
+}
+
+/// Linear mix of two images, `amount` 0.0 giving `from` and 1.0 giving `to`.
+/// Both images must have the same dimensions.
+pub(crate) fn mix(from: &RgbaImage, to: &RgbaImage, amount: f32) -> RgbaImage {
+    let amount = amount.clamp(0.0, 1.0);
+    let mut out = from.clone();
+    for (dst, src) in out.pixels_mut().zip(to.pixels()) {
+        for channel in 0..4 {
+            let a = dst.0[channel] as f32;
+            let b = src.0[channel] as f32;
+            dst.0[channel] = (a + (b - a) * amount).round() as u8;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn solid(color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(2, 2, Rgba(color))
+    }
+
+    #[test]
+    fn test_blend_layer_opacity_and_modes() {
+        let mut canvas = solid([200, 100, 0, 255]);
+        blend_layer(
+            &mut canvas,
+            &solid([0, 0, 200, 255]),
+            BlendMode::Normal,
+            0.5,
+        );
+        assert_eq!(canvas.get_pixel(0, 0).0, [100, 50, 100, 255]);
+
+        let mut canvas = solid([200, 100, 0, 255]);
+        blend_layer(
+            &mut canvas,
+            &solid([255, 128, 255, 255]),
+            BlendMode::Multiply,
+            1.0,
+        );
+        assert_eq!(canvas.get_pixel(0, 0).0, [200, 50, 0, 255]);
+
+        // Over a transparent canvas the mode has nothing to blend with
+        let mut canvas = RgbaImage::new(2, 2);
+        blend_layer(
+            &mut canvas,
+            &solid([10, 20, 30, 255]),
+            BlendMode::Multiply,
+            1.0,
+        );
+        assert_eq!(canvas.get_pixel(0, 0).0, [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_pre_blend_keeps_layer_alpha() {
+        let backdrop = solid([200, 100, 0, 255]);
+        let layer = solid([255, 128, 255, 64]);
+        let blended = pre_blend(&backdrop, &layer, BlendMode::Multiply);
+        assert_eq!(blended.get_pixel(0, 0).0, [200, 50, 0, 64]);
+        assert_eq!(pre_blend(&backdrop, &layer, BlendMode::Normal), layer);
+    }
+}
@@ -0,0 +1,565 @@
+//! Compositor - renders sequence frames by stacking clip layers
+
+use image::{Rgba, RgbaImage, imageops::FilterType};
+use std::collections::HashMap;
+
+use super::GeneratorRenderer;
+use super::blend::{blend_layer, mix, pre_blend};
+use crate::{EngineError, Result};
+use vxutil_core::generators::{Generator, TextGenerator};
+use vxutil_core::media::{MediaId, MediaItem, MediaLibrary};
+use vxutil_core::timeline::{Clip, ClipId, ClipSource, ScaleMode, Sequence, SequenceId};
+use vxutil_core::{FrameRate, Resolution, TimeRange, Timecode};
+
+/// Supplies decoded source frames to the compositor
+pub trait FrameProvider {
+    /// Decoded RGBA frame of a media item at the given source time
+    fn frame(&mut self, media_id: &MediaId, source_time: Timecode) -> Result<RgbaImage>;
+
+    /// Native frame rate of a media item, used to find neighbouring frames when
+    /// blending retimed clips. None falls back to the sequence frame rate.
+    fn frame_rate(&mut self, _media_id: &MediaId) -> Option<FrameRate> {
+        None
+    }
+
+    /// Whether a media item is offline. Offline clips render a placeholder and
+    /// are never decoded.
+    fn is_offline(&mut self, _media_id: &MediaId) -> bool {
+        false
+    }
+}
+
+/// Frames of the items in a media library, decoded with `decode` (e.g.
+/// [`crate::ffmpeg::media_frame`]). Items marked offline, or missing from the
+/// library, are reported offline.
+pub struct LibraryFrames<'a> {
+    library: &'a MediaLibrary,
+    decode: &'a mut dyn FnMut(&MediaItem, Timecode) -> Result<RgbaImage>,
+}
+
+impl<'a> LibraryFrames<'a> {
+    pub fn new(
+        library: &'a MediaLibrary,
+        decode: &'a mut dyn FnMut(&MediaItem, Timecode) -> Result<RgbaImage>,
+    ) -> Self {
+        Self { library, decode }
+    }
+}
+
+impl FrameProvider for LibraryFrames<'_> {
+    fn frame(&mut self, media_id: &MediaId, source_time: Timecode) -> Result<RgbaImage> {
+        let item = self
+            .library
+            .get_item(media_id)
+            .ok_or_else(|| EngineError::Rendering("Media item not found".to_string()))?;
+        (self.decode)(item, source_time)
+    }
+
+    fn frame_rate(&mut self, media_id: &MediaId) -> Option<FrameRate> {
+        self.library.get_item(media_id)?.metadata.frame_rate
+    }
+
+    fn is_offline(&mut self, media_id: &MediaId) -> bool {
+        self.library
+            .get_item(media_id)
+            .is_none_or(|item| item.offline)
+    }
+}
+
+/// Renders sequence frames, resolving compound clips recursively
+pub struct Compositor {
+    generators: GeneratorRenderer,
+
+    /// Decoded frames of freeze frame clips with the source time they show,
+    /// so a hold is decoded once instead of on every frame
+    held_frames: HashMap<ClipId, (Timecode, RgbaImage)>,
+}
+
+impl Default for Compositor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compositor {
+    pub fn new() -> Self {
+        Self {
+            generators: GeneratorRenderer::new(),
+            held_frames: HashMap::new(),
+        }
+    }
+
+    /// Drop cached freeze frames, e.g. after relinking media
+    pub fn clear_held_frames(&mut self) {
+        self.held_frames.clear();
+    }
+
+    /// Render the frame of a sequence at the given time over a black background
+    pub fn render_frame(
+        &mut self,
+        sequences: &[Sequence],
+        sequence_id: &SequenceId,
+        time: Timecode,
+        frames: &mut dyn FrameProvider,
+    ) -> Result<RgbaImage> {
+        let mut stack = Vec::new();
+        let layers = self.render_sequence(sequences, sequence_id, time, frames, &mut stack)?;
+
+        let mut frame =
+            RgbaImage::from_pixel(layers.width(), layers.height(), Rgba([0, 0, 0, 255]));
+        blend_layer(&mut frame, &layers, Default::default(), 1.0);
+        Ok(frame)
+    }
+
+    /// Render every frame of a range (e.g. the sequence in/out range), handing each
+    /// frame to `sink` in order
+    pub fn render_range(
+        &mut self,
+        sequences: &[Sequence],
+        sequence_id: &SequenceId,
+        range: TimeRange,
+        frames: &mut dyn FrameProvider,
+        sink: &mut dyn FnMut(Timecode, RgbaImage) -> Result<()>,
+    ) -> Result<()> {
+        let sequence = sequences
+            .iter()
+            .find(|s| &s.id == sequence_id)
+            .ok_or_else(|| EngineError::Rendering("Sequence not found".to_string()))?;
+
+        for time in sequence.frame_times(range) {
+            let frame = self.render_frame(sequences, sequence_id, time, frames)?;
+            sink(time, frame)?;
+        }
+        Ok(())
+    }
+
+    /// Composite all video tracks of a sequence onto a transparent canvas.
+    /// `stack` holds the sequences currently being rendered, to detect cycles.
+    fn render_sequence(
+        &mut self,
+        sequences: &[Sequence],
+        sequence_id: &SequenceId,
+        time: Timecode,
+        frames: &mut dyn FrameProvider,
+        stack: &mut Vec<SequenceId>,
+    ) -> Result<RgbaImage> {
+        if stack.contains(sequence_id) {
+            return Err(EngineError::Rendering(
+                "Sequence cycle detected while rendering nested sequences".to_string(),
+            ));
+        }
+        let sequence = sequences
+            .iter()
+            .find(|s| &s.id == sequence_id)
+            .ok_or_else(|| EngineError::Rendering("Sequence not found".to_string()))?;
+
+        stack.push(sequence_id.clone());
+        let result = self.composite(sequences, sequence, time, frames, stack);
+        stack.pop();
+        result
+    }
+
+    fn composite(
+        &mut self,
+        sequences: &[Sequence],
+        sequence: &Sequence,
+        time: Timecode,
+        frames: &mut dyn FrameProvider,
+        stack: &mut Vec<SequenceId>,
+    ) -> Result<RgbaImage> {
+        let resolution = sequence.resolution;
+        let mut canvas = RgbaImage::new(resolution.width, resolution.height);
+
+        // Lowest visible track first, higher tracks are composited on top
+        for track in sequence.visible_video_tracks() {
+            let Some(clip) = track.clip_at_time(time) else {
+                continue;
+            };
+            let Some(source_time) = clip.timeline_to_source_time(time) else {
+                continue;
+            };
+
+            let layer = match &clip.source {
+                ClipSource::Media(media_id) if frames.is_offline(media_id) => {
+                    self.offline_placeholder(resolution)
+                }
+                ClipSource::Media(media_id) if clip.is_freeze_frame() => {
+                    self.held_frame(clip, media_id, source_time, frames)?
+                }
+                ClipSource::Media(media_id) => {
+                    let blend = clip
+                        .speed_at(time)
+                        .is_some_and(|speed| clip.retime_mode.blends_at(speed));
+                    if blend {
+                        blended_frame(frames, media_id, source_time, sequence.frame_rate)?
+                    } else {
+                        frames.frame(media_id, source_time)?
+                    }
+                }
+                ClipSource::Generator(generator) => {
+                    self.generators.render(generator, resolution)?
+                }
+                ClipSource::Sequence(nested_id) => {
+                    self.render_sequence(sequences, nested_id, source_time, frames, stack)?
+                }
+            };
+
+            let layer = scale_layer(layer, clip.scale_mode, resolution);
+            match track.blend_mode {
+                // The clip blends with its own mode first, the track mode goes on top
+                Some(mode) => {
+                    let layer = pre_blend(&canvas, &layer, clip.blend_mode);
+                    blend_layer(&mut canvas, &layer, mode, track.opacity);
+                }
+                None => blend_layer(&mut canvas, &layer, clip.blend_mode, track.opacity),
+            }
+        }
+
+        Ok(canvas)
+    }
+
+    /// Dark red frame labelled "Media Offline"
+    fn offline_placeholder(&mut self, resolution: Resolution) -> RgbaImage {
+        let mut frame =
+            RgbaImage::from_pixel(resolution.width, resolution.height, Rgba([96, 0, 0, 255]));
+        // Without a usable font the plain color still marks the clip as offline
+        let label = Generator::Text(TextGenerator::new("Media Offline"));
+        if let Ok(text) = self.generators.render(&label, resolution) {
+            blend_layer(&mut frame, &text, Default::default(), 1.0);
+        }
+        frame
+    }
+
+    /// Frame of a freeze frame clip, decoded on first use
+    fn held_frame(
+        &mut self,
+        clip: &Clip,
+        media_id: &MediaId,
+        source_time: Timecode,
+        frames: &mut dyn FrameProvider,
+    ) -> Result<RgbaImage> {
+        if let Some((time, frame)) = self.held_frames.get(&clip.id)
+            && *time == source_time
+        {
+            return Ok(frame.clone());
+        }
+        let frame = frames.frame(media_id, source_time)?;
+        self.held_frames
+            .insert(clip.id.clone(), (source_time, frame.clone()));
+        Ok(frame)
+    }
+}
+
+/// Mix the two source frames around `source_time` by its position between them
+fn blended_frame(
+    frames: &mut dyn FrameProvider,
+    media_id: &MediaId,
+    source_time: Timecode,
+    fallback_rate: FrameRate,
+) -> Result<RgbaImage> {
+    let rate = frames.frame_rate(media_id).unwrap_or(fallback_rate);
+    let position = source_time.as_seconds() * rate.as_f64();
+    let index = position.floor() as u64;
+    let amount = (position - position.floor()) as f32;
+
+    let first = frames.frame(media_id, Timecode::from_frames(index, rate))?;
+    if amount < 1e-3 {
+        return Ok(first);
+    }
+    let second = frames.frame(media_id, Timecode::from_frames(index + 1, rate))?;
+    if second.dimensions() != first.dimensions() {
+        return Ok(first);
+    }
+    Ok(mix(&first, &second, amount))
+}
+
+/// Scale a layer onto a transparent canvas-sized frame by the clip's scale mode
+fn scale_layer(layer: RgbaImage, mode: ScaleMode, resolution: Resolution) -> RgbaImage {
+    let (width, height) = layer.dimensions();
+    if (width, height) == (resolution.width, resolution.height) {
+        return layer;
+    }
+
+    let placement = mode.placement(Resolution::new(width, height), resolution);
+    let scaled = if (placement.width, placement.height) == (width, height) {
+        layer
+    } else {
+        image::imageops::resize(
+            &layer,
+            placement.width,
+            placement.height,
+            FilterType::Triangle,
+        )
+    };
+    if scaled.dimensions() == (resolution.width, resolution.height) {
+        return scaled;
+    }
+
+    let mut frame = RgbaImage::new(resolution.width, resolution.height);
+    image::imageops::replace(&mut frame, &scaled, placement.x, placement.y);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use vxutil_core::timeline::{BlendMode, Track, TrackId, TrackType};
+
+    const SIZE: Resolution = Resolution {
+        width: 4,
+        height: 4,
+    };
+
+    /// Solid frames, one color per media item, recording every request
+    #[derive(Default)]
+    struct SolidFrames {
+        colors: HashMap<MediaId, Rgba<u8>>,
+        offline: Vec<MediaId>,
+        requests: Vec<(MediaId, Timecode)>,
+    }
+
+    impl SolidFrames {
+        fn add(&mut self, color: [u8; 4]) -> MediaId {
+            let id = MediaId::new();
+            self.colors.insert(id.clone(), Rgba(color));
+            id
+        }
+    }
+
+    impl FrameProvider for SolidFrames {
+        fn frame(&mut self, media_id: &MediaId, source_time: Timecode) -> Result<RgbaImage> {
+            self.requests.push((media_id.clone(), source_time));
+            let color = self.colors[media_id];
+            Ok(RgbaImage::from_pixel(SIZE.width, SIZE.height, color))
+        }
+
+        fn is_offline(&mut self, media_id: &MediaId) -> bool {
+            self.offline.contains(media_id)
+        }
+    }
+
+    fn sequence(name: &str) -> Sequence {
+        Sequence::new(name.to_string(), FrameRate::FPS_30, SIZE)
+    }
+
+    /// Video track holding a single clip
+    fn track_with(clip: Clip) -> Track {
+        let mut track = Track::new(TrackId(0), "V1".to_string(), TrackType::Video);
+        track.add_clip(clip);
+        track
+    }
+
+    fn media_clip(media_id: &MediaId, position: f64, duration: f64) -> Clip {
+        Clip::new(
+            "clip".to_string(),
+            media_id.clone(),
+            Timecode::from_seconds(position),
+            Timecode::from_seconds(0.0),
+            Timecode::from_seconds(duration),
+        )
+    }
+
+    fn compound(sequence_id: &SequenceId, position: f64, duration: u64) -> Clip {
+        Clip::new_compound(
+            "nested".to_string(),
+            sequence_id.clone(),
+            Timecode::from_seconds(position),
+            Duration::from_secs(duration),
+        )
+    }
+
+    fn center(frame: &RgbaImage) -> [u8; 4] {
+        frame.get_pixel(2, 2).0
+    }
+
+    #[test]
+    fn test_nested_sequence_renders_in_its_own_time() {
+        let mut frames = SolidFrames::default();
+        let red = frames.add([255, 0, 0, 255]);
+
+        // The inner media starts 1s into the inner sequence
+        let mut inner = sequence("inner");
+        inner.add_track(track_with(media_clip(&red, 1.0, 5.0)));
+        let mut outer = sequence("outer");
+        outer.add_track(track_with(compound(&inner.id, 2.0, 6)));
+        let outer_id = outer.id.clone();
+        let sequences = [outer, inner];
+
+        let mut compositor = Compositor::new();
+        let frame = compositor
+            .render_frame(
+                &sequences,
+                &outer_id,
+                Timecode::from_seconds(3.5),
+                &mut frames,
+            )
+            .unwrap();
+        assert_eq!(center(&frame), [255, 0, 0, 255]);
+        assert_eq!(frames.requests, vec![(red, Timecode::from_seconds(0.5))]);
+
+        // Inside the compound clip but before the inner media starts
+        let frame = compositor
+            .render_frame(
+                &sequences,
+                &outer_id,
+                Timecode::from_seconds(2.5),
+                &mut frames,
+            )
+            .unwrap();
+        assert_eq!(center(&frame), [0, 0, 0, 255]);
+        assert_eq!(frames.requests.len(), 1);
+    }
+
+    #[test]
+    fn test_sequence_cycle_is_a_rendering_error() {
+        let mut first = sequence("first");
+        let mut second = sequence("second");
+        first.add_track(track_with(compound(&second.id, 0.0, 5)));
+        second.add_track(track_with(compound(&first.id, 0.0, 5)));
+        let first_id = first.id.clone();
+        let sequences = [first, second];
+
+        let result = Compositor::new().render_frame(
+            &sequences,
+            &first_id,
+            Timecode::from_seconds(1.0),
+            &mut SolidFrames::default(),
+        );
+        assert!(matches!(result, Err(EngineError::Rendering(_))));
+    }
+
+    /// Sequence with a `bottom` and a `top` media clip on two video tracks
+    fn two_layers(bottom: &MediaId, top: &MediaId) -> Sequence {
+        let mut sequence = sequence("layers");
+        sequence.add_track(track_with(media_clip(bottom, 0.0, 5.0)));
+        sequence.add_track(track_with(media_clip(top, 0.0, 5.0)));
+        sequence
+    }
+
+    fn render(sequence: Sequence, frames: &mut SolidFrames) -> [u8; 4] {
+        let id = sequence.id.clone();
+        let frame = Compositor::new()
+            .render_frame(&[sequence], &id, Timecode::from_seconds(1.0), frames)
+            .unwrap();
+        center(&frame)
+    }
+
+    #[test]
+    fn test_hidden_track_is_not_decoded() {
+        let mut frames = SolidFrames::default();
+        let red = frames.add([255, 0, 0, 255]);
+        let blue = frames.add([0, 0, 255, 255]);
+        let mut sequence = two_layers(&red, &blue);
+        sequence.video_tracks[1].visible = false;
+
+        assert_eq!(render(sequence, &mut frames), [255, 0, 0, 255]);
+        assert!(frames.requests.iter().all(|(id, _)| *id == red));
+    }
+
+    #[test]
+    fn test_track_opacity() {
+        let mut frames = SolidFrames::default();
+        let red = frames.add([200, 0, 0, 255]);
+        let blue = frames.add([0, 0, 200, 255]);
+        let mut sequence = two_layers(&red, &blue);
+        sequence.video_tracks[1].set_opacity(0.5);
+
+        assert_eq!(render(sequence, &mut frames), [100, 0, 100, 255]);
+    }
+
+    #[test]
+    fn test_track_blend_mode_applies_on_top_of_clip_mode() {
+        let mut frames = SolidFrames::default();
+        let bottom = frames.add([200, 100, 0, 255]);
+        let top = frames.add([255, 128, 255, 255]);
+
+        // Track mode alone
+        let mut sequence = two_layers(&bottom, &top);
+        sequence.video_tracks[1].blend_mode = Some(BlendMode::Multiply);
+        assert_eq!(render(sequence, &mut frames), [200, 50, 0, 255]);
+
+        // Screen gives (255, 178, 255) over the bottom, multiplied by it again
+        let mut sequence = two_layers(&bottom, &top);
+        let track = &mut sequence.video_tracks[1];
+        track.blend_mode = Some(BlendMode::Multiply);
+        let clip_id = track.clips()[0].id.clone();
+        track
+            .update_clip(&clip_id, |clip| clip.blend_mode = BlendMode::Screen)
+            .unwrap();
+        assert_eq!(render(sequence, &mut frames), [200, 70, 0, 255]);
+
+        // Without a track mode the clip mode is used on its own
+        let mut sequence = two_layers(&bottom, &top);
+        let track = &mut sequence.video_tracks[1];
+        let clip_id = track.clips()[0].id.clone();
+        track
+            .update_clip(&clip_id, |clip| clip.blend_mode = BlendMode::Screen)
+            .unwrap();
+        assert_eq!(render(sequence, &mut frames), [255, 178, 255, 255]);
+    }
+
+    /// Sequence large enough that the placeholder label leaves its corners alone
+    fn offline_sequence(media_id: &MediaId) -> Sequence {
+        let resolution = Resolution::new(320, 180);
+        let mut sequence = Sequence::new("offline".to_string(), FrameRate::FPS_30, resolution);
+        sequence.add_track(track_with(media_clip(media_id, 0.0, 5.0)));
+        sequence
+    }
+
+    #[test]
+    fn test_offline_clip_renders_placeholder() {
+        let mut frames = SolidFrames::default();
+        let missing = frames.add([0, 255, 0, 255]);
+        frames.offline.push(missing.clone());
+        let sequence = offline_sequence(&missing);
+        let id = sequence.id.clone();
+
+        let frame = Compositor::new()
+            .render_frame(&[sequence], &id, Timecode::from_seconds(1.0), &mut frames)
+            .unwrap();
+        assert_eq!(frame.get_pixel(0, 0).0, [96, 0, 0, 255]);
+        assert!(frames.requests.is_empty());
+    }
+
+    #[test]
+    fn test_library_frames_follow_offline_flag() {
+        let mut library = MediaLibrary::new();
+        let mut item = MediaItem::new("clip.mov".into(), vxutil_core::media::MediaType::Video);
+        item.offline = true;
+        let media_id = library.add_item(item);
+        let sequence = offline_sequence(&media_id);
+        let id = sequence.id.clone();
+        let sequences = [sequence];
+
+        let mut decoded = 0;
+        let mut decode = |_: &MediaItem, _: Timecode| {
+            decoded += 1;
+            Ok(RgbaImage::from_pixel(320, 180, Rgba([0, 255, 0, 255])))
+        };
+        let mut compositor = Compositor::new();
+        let time = Timecode::from_seconds(1.0);
+        let frame = compositor
+            .render_frame(
+                &sequences,
+                &id,
+                time,
+                &mut LibraryFrames::new(&library, &mut decode),
+            )
+            .unwrap();
+        assert_eq!(frame.get_pixel(0, 0).0, [96, 0, 0, 255]);
+
+        // Relinked items are decoded again
+        library.get_item_mut(&media_id).unwrap().offline = false;
+        let frame = compositor
+            .render_frame(
+                &sequences,
+                &id,
+                time,
+                &mut LibraryFrames::new(&library, &mut decode),
+            )
+            .unwrap();
+        assert_eq!(frame.get_pixel(0, 0).0, [0, 255, 0, 255]);
+        assert_eq!(decoded, 1);
+    }
+}
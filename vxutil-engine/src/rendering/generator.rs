@@ -0,0 +1,278 @@
+//! Rasterization of generator clips (titles and mattes)
+
+use ab_glyph::{Font, FontArc, FontVec, GlyphId, PxScale, ScaleFont, point};
+use fontdb::{Database, Family, Query};
+use image::{GrayImage, Luma, RgbaImage};
+use std::collections::HashMap;
+
+use crate::{EngineError, Result};
+use vxutil_core::generators::{
+    Generator, GradientGenerator, GradientKind, TextAlignment, TextGenerator, VerticalAlignment,
+};
+use vxutil_core::{Color, Resolution};
+
+/// Renders generator clips into RGBA frames
+pub struct GeneratorRenderer {
+    fonts: Database,
+    loaded: HashMap<String, FontArc>,
+}
+
+impl Default for GeneratorRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GeneratorRenderer {
+    pub fn new() -> Self {
+        let mut fonts = Database::new();
+        fonts.load_system_fonts();
+        Self {
+            fonts,
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Render a generator at the given output resolution
+    pub fn render(&mut self, generator: &Generator, resolution: Resolution) -> Result<RgbaImage> {
+        match generator {
+            Generator::SolidColor(solid) => Ok(RgbaImage::from_pixel(
+                resolution.width,
+                resolution.height,
+                image::Rgba(solid.color.to_rgba8()),
+            )),
+            Generator::Gradient(gradient) => Ok(render_gradient(gradient, resolution)),
+            Generator::Text(text) => self.render_text(text, resolution),
+        }
+    }
+
+    /// Look up a font by family name, falling back to the default sans-serif face
+    fn font(&mut self, family: &str) -> Result<FontArc> {
+        if let Some(font) = self.loaded.get(family) {
+            return Ok(font.clone());
+        }
+
+        let id = self
+            .fonts
+            .query(&Query {
+                families: &[Family::Name(family), Family::SansSerif],
+                ..Query::default()
+            })
+            .ok_or_else(|| EngineError::Rendering(format!("Font not found: {}", family)))?;
+
+        let (data, index) = self
+            .fonts
+            .with_face_data(id, |data, index| (data.to_vec(), index))
+            .ok_or_else(|| EngineError::Rendering(format!("Font not loadable: {}", family)))?;
+
+        let font = FontVec::try_from_vec_and_index(data, index)
+            .map(FontArc::new)
+            .map_err(|e| EngineError::Rendering(format!("Invalid font {}: {}", family, e)))?;
+
+        self.loaded.insert(family.to_string(), font.clone());
+        Ok(font)
+    }
+
+    fn render_text(&mut self, text: &TextGenerator, resolution: Resolution) -> Result<RgbaImage> {
+        let font = self.font(&text.font_family)?;
+        let scale = PxScale::from(text.font_size);
+        let scaled = font.as_scaled(scale);
+
+        let lines: Vec<&str> = text.lines().collect();
+        let line_height = text.line_height();
+        let block_height = line_height * lines.len().saturating_sub(1) as f32 + scaled.height();
+
+        let anchor_x = text.anchor_x * resolution.width as f32;
+        let anchor_y = text.anchor_y * resolution.height as f32;
+        let top = match text.vertical_alignment {
+            VerticalAlignment::Top => anchor_y,
+            VerticalAlignment::Middle => anchor_y - block_height / 2.0,
+            VerticalAlignment::Bottom => anchor_y - block_height,
+        };
+
+        // Coverage mask of the glyph fills
+        let mut fill = GrayImage::new(resolution.width, resolution.height);
+        // Coverage mask of the fills grown by the outline width
+        let mut outline = text
+            .outline
+            .map(|_| GrayImage::new(resolution.width, resolution.height));
+
+        let outline_offsets = text
+            .outline
+            .map(|o| circle_offsets(o.width))
+            .unwrap_or_default();
+
+        for (index, line) in lines.iter().enumerate() {
+            let width = line_width(&font, scale, line);
+            let x = match text.alignment {
+                TextAlignment::Left => anchor_x,
+                TextAlignment::Center => anchor_x - width / 2.0,
+                TextAlignment::Right => anchor_x - width,
+            };
+            let baseline = top + scaled.ascent() + line_height * index as f32;
+
+            draw_line(&mut fill, &font, scale, line, x, baseline);
+            if let Some(outline) = outline.as_mut() {
+                for (dx, dy) in &outline_offsets {
+                    draw_line(outline, &font, scale, line, x + dx, baseline + dy);
+                }
+            }
+        }
+
+        let mut frame = RgbaImage::new(resolution.width, resolution.height);
+
+        if let Some(shadow) = text.shadow {
+            let silhouette = outline.as_ref().unwrap_or(&fill);
+            let mask = if shadow.blur > 0.0 {
+                image::imageops::blur(silhouette, shadow.blur / 2.0)
+            } else {
+                silhouette.clone()
+            };
+            paint(
+                &mut frame,
+                &mask,
+                shadow.color,
+                shadow.offset_x.round() as i64,
+                shadow.offset_y.round() as i64,
+            );
+        }
+
+        if let (Some(style), Some(mask)) = (text.outline, outline.as_ref()) {
+            paint(&mut frame, mask, style.color, 0, 0);
+        }
+
+        paint(&mut frame, &fill, text.color, 0, 0);
+
+        Ok(frame)
+    }
+}
+
+fn render_gradient(gradient: &GradientGenerator, resolution: Resolution) -> RgbaImage {
+    let (width, height) = (resolution.width as f32, resolution.height as f32);
+    let (center_x, center_y) = (width / 2.0, height / 2.0);
+
+    RgbaImage::from_fn(resolution.width, resolution.height, |x, y| {
+        let dx = x as f32 + 0.5 - center_x;
+        let dy = y as f32 + 0.5 - center_y;
+
+        let t = match gradient.kind {
+            GradientKind::Linear { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                // Half-length of the frame projected on the gradient direction
+                let extent = center_x * cos.abs() + center_y * sin.abs();
+                (dx * cos + dy * sin) / (2.0 * extent) + 0.5
+            }
+            GradientKind::Radial => {
+                (dx * dx + dy * dy).sqrt() / (center_x * center_x + center_y * center_y).sqrt()
+            }
+        };
+
+        image::Rgba(gradient.color_at(t).to_rgba8())
+    })
+}
+
+/// Width of a line of text in pixels, including kerning
+fn line_width(font: &FontArc, scale: PxScale, line: &str) -> f32 {
+    let scaled = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for ch in line.chars() {
+        let id = scaled.glyph_id(ch);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Rasterize a line of text into a coverage mask, starting at `x` on the given baseline
+fn draw_line(
+    mask: &mut GrayImage,
+    font: &FontArc,
+    scale: PxScale,
+    line: &str,
+    x: f32,
+    baseline: f32,
+) {
+    let scaled = font.as_scaled(scale);
+    let mut caret = x;
+    let mut previous: Option<GlyphId> = None;
+
+    for ch in line.chars() {
+        let id = scaled.glyph_id(ch);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= mask.width() as i64 || py >= mask.height() as i64 {
+                return;
+            }
+            let pixel = mask.get_pixel_mut(px as u32, py as u32);
+            let value = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+            *pixel = Luma([pixel.0[0].max(value)]);
+        });
+    }
+}
+
+/// Offsets on filled circles up to `radius`, used to grow glyphs for outlines
+fn circle_offsets(radius: f32) -> Vec<(f32, f32)> {
+    let steps = radius.ceil().max(1.0) as usize;
+    let mut offsets = Vec::new();
+    for step in 1..=steps {
+        let r = radius * step as f32 / steps as f32;
+        let samples = (r * 8.0).ceil().max(8.0) as usize;
+        for i in 0..samples {
+            let angle = std::f32::consts::TAU * i as f32 / samples as f32;
+            offsets.push((r * angle.cos(), r * angle.sin()));
+        }
+    }
+    offsets
+}
+
+/// Composite a solid color through a coverage mask onto the frame ("over" operator)
+fn paint(frame: &mut RgbaImage, mask: &GrayImage, color: Color, dx: i64, dy: i64) {
+    let (width, height) = (frame.width() as i64, frame.height() as i64);
+    for (x, y, coverage) in mask.enumerate_pixels() {
+        if coverage.0[0] == 0 {
+            continue;
+        }
+        let (tx, ty) = (x as i64 + dx, y as i64 + dy);
+        if tx < 0 || ty < 0 || tx >= width || ty >= height {
+            continue;
+        }
+
+        let src_alpha = color.a * coverage.0[0] as f32 / 255.0;
+        let dst = frame.get_pixel_mut(tx as u32, ty as u32);
+        let dst_alpha = dst.0[3] as f32 / 255.0;
+        let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+        if out_alpha <= 0.0 {
+            continue;
+        }
+
+        let blend = |src: f32, dst: u8| {
+            let dst = dst as f32 / 255.0;
+            (src * src_alpha + dst * dst_alpha * (1.0 - src_alpha)) / out_alpha
+        };
+        *dst = image::Rgba(
+            Color::new(
+                blend(color.r, dst.0[0]),
+                blend(color.g, dst.0[1]),
+                blend(color.b, dst.0[2]),
+                out_alpha,
+            )
+            .to_rgba8(),
+        );
+    }
+}
@@ -0,0 +1,10 @@
+//! Rendering and compositing pipeline
+
+// TODO: Implement export functionality
+
+mod blend;
+mod compositor;
+mod generator;
+
+pub use compositor::{Compositor, FrameProvider, LibraryFrames};
+pub use generator::GeneratorRenderer;
@@ -0,0 +1,22 @@
+//! Still image decoding
+
+use image::{DynamicImage, ImageDecoder, ImageReader, RgbaImage};
+use std::path::Path;
+
+use crate::{EngineError, Result};
+
+/// Decode an image file to RGBA, rotating and flipping it upright as its EXIF
+/// orientation says (common in phone and camera photos)
+pub fn decode_image(path: &Path) -> Result<RgbaImage> {
+    let decode_error =
+        |e: image::ImageError| EngineError::Decode(format!("{}: {}", path.display(), e));
+
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(decode_error)?;
+    let orientation = decoder.orientation().map_err(decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+    Ok(image.to_rgba8())
+}
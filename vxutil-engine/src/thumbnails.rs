@@ -0,0 +1,274 @@
+//! Poster and filmstrip thumbnails with an on-disk cache
+
+use crossbeam::channel::{self, Receiver, Sender};
+use image::imageops::FilterType;
+use image::{ImageFormat, RgbaImage};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use vxutil_core::Timecode;
+use vxutil_core::media::{MediaId, MediaItem, MediaLibrary, MediaType};
+
+use crate::content_cache::{self, ContentCache, ContentKey};
+use crate::{EngineError, Result};
+
+/// Decodes the frame of a media item at a source time, e.g. [`crate::ffmpeg::media_frame`]
+pub type FrameDecoder = Box<dyn FnMut(&MediaItem, Timecode) -> Result<RgbaImage> + Send>;
+
+/// Cached thumbnail files of one media item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnails {
+    pub poster: PathBuf,
+
+    /// Evenly spaced frames with their source times; empty for stills
+    pub filmstrip: Vec<(Timecode, PathBuf)>,
+}
+
+/// Thumbnail files stored below a cache directory, one folder per [`ContentKey`].
+/// A changed source file gets a new key and so new thumbnails.
+#[derive(Debug, Clone)]
+pub struct ThumbnailCache {
+    entries: ContentCache,
+
+    /// Largest thumbnail width or height in pixels
+    pub size: u32,
+
+    /// Number of filmstrip frames per video
+    pub filmstrip_frames: usize,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            entries: ContentCache::new(dir, None),
+            size: 256,
+            filmstrip_frames: 10,
+        }
+    }
+
+    /// Folder holding the thumbnails of some content
+    pub fn entry_dir(&self, key: &ContentKey) -> PathBuf {
+        self.entries.entry_path(key)
+    }
+
+    /// Cached thumbnails of some content, if all files exist
+    pub fn get(&self, key: &ContentKey, duration: Option<f64>) -> Option<Thumbnails> {
+        let thumbnails = self.layout(key, duration);
+        let complete = thumbnails.poster.is_file()
+            && thumbnails.filmstrip.iter().all(|(_, path)| path.is_file());
+        complete.then_some(thumbnails)
+    }
+
+    /// Delete the thumbnails of some content
+    pub fn remove(&self, key: &ContentKey) -> Result<()> {
+        self.entries.remove(key)
+    }
+
+    /// Delete the thumbnails of content no item in the library has any more, as
+    /// described at [`ContentCache::prune`]. Returns the number of folders deleted.
+    pub fn prune(&self, library: &MediaLibrary) -> Result<usize> {
+        self.entries.prune(library, |_| true)
+    }
+
+    /// Thumbnails of a media item, generating any that are missing. The key of the
+    /// item's current content is computed and returned with its thumbnails.
+    /// Thumbnails of older content are left for [`ThumbnailCache::prune`], as
+    /// duplicates of the item may still use them.
+    pub fn generate(
+        &self,
+        item: &MediaItem,
+        decode: &mut dyn FnMut(&MediaItem, Timecode) -> Result<RgbaImage>,
+    ) -> Result<(ContentKey, Thumbnails)> {
+        let key = ContentKey::compute(item)?;
+        let duration = item.duration_seconds();
+        if let Some(thumbnails) = self.get(&key, duration) {
+            return Ok((key, thumbnails));
+        }
+
+        fs::create_dir_all(self.entry_dir(&key))?;
+        let thumbnails = self.layout(&key, duration);
+        // Poster frame a little into the clip, past fades from black
+        let poster_time = Timecode::from_seconds(duration.map_or(0.0, |d| (d * 0.1).min(1.0)));
+        self.write(decode(item, poster_time)?, &thumbnails.poster)?;
+        for (time, path) in &thumbnails.filmstrip {
+            self.write(decode(item, *time)?, path)?;
+        }
+        Ok((key, thumbnails))
+    }
+
+    /// File paths for some content. Filmstrip frames sit at the middle of equal
+    /// slices of the duration.
+    fn layout(&self, key: &ContentKey, duration: Option<f64>) -> Thumbnails {
+        let dir = self.entry_dir(key);
+        let filmstrip = match duration {
+            Some(duration) if duration > 0.0 && self.filmstrip_frames > 0 => {
+                let count = self.filmstrip_frames;
+                (0..count)
+                    .map(|index| {
+                        let time = duration * (index as f64 + 0.5) / count as f64;
+                        (
+                            Timecode::from_seconds(time),
+                            dir.join(format!("strip-{index:03}.png")),
+                        )
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        Thumbnails {
+            poster: dir.join("poster.png"),
+            filmstrip,
+        }
+    }
+
+    /// Scale a frame down to the thumbnail size and save it as PNG
+    fn write(&self, frame: RgbaImage, path: &Path) -> Result<()> {
+        let (width, height) = frame.dimensions();
+        let scale = self.size as f64 / width.max(height).max(1) as f64;
+        let thumbnail = if scale < 1.0 {
+            image::imageops::resize(
+                &frame,
+                ((width as f64 * scale).round() as u32).max(1),
+                ((height as f64 * scale).round() as u32).max(1),
+                FilterType::Triangle,
+            )
+        } else {
+            frame
+        };
+        content_cache::write_atomic(path, |partial| {
+            thumbnail
+                .save_with_format(partial, ImageFormat::Png)
+                .map_err(|e| EngineError::Encode(format!("{}: {}", path.display(), e)))
+        })
+    }
+}
+
+/// Finished thumbnail job
+pub struct ThumbnailEvent {
+    pub media_id: MediaId,
+    pub result: Result<(ContentKey, Thumbnails)>,
+}
+
+impl ThumbnailEvent {
+    /// Store the poster path and current fingerprint on the library item
+    pub fn apply(&self, library: &mut MediaLibrary) {
+        if let (Ok((key, thumbnails)), Some(item)) =
+            (&self.result, library.get_item_mut(&self.media_id))
+        {
+            item.fingerprint = Some(key.fingerprint);
+            item.thumbnail_path = Some(thumbnails.poster.clone());
+        }
+    }
+}
+
+/// Generates thumbnails on a background thread. Jobs run in request order;
+/// finished jobs are collected with [`ThumbnailService::poll`].
+pub struct ThumbnailService {
+    requests: Option<Sender<MediaItem>>,
+
+    /// The worker's end of the request queue, kept to discard pending jobs on drop
+    pending: Receiver<MediaItem>,
+    events: Receiver<ThumbnailEvent>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ThumbnailService {
+    pub fn start(cache: ThumbnailCache, mut decode: FrameDecoder) -> Self {
+        let (requests, jobs) = channel::unbounded::<MediaItem>();
+        let (done, events) = channel::unbounded();
+        let pending = jobs.clone();
+        let worker = thread::spawn(move || {
+            for item in jobs {
+                let result = cache.generate(&item, &mut decode);
+                let event = ThumbnailEvent {
+                    media_id: item.id,
+                    result,
+                };
+                if done.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests: Some(requests),
+            pending,
+            events,
+            worker: Some(worker),
+        }
+    }
+
+    /// Queue thumbnails for a media item. Audio and offline items are skipped.
+    pub fn request(&self, item: &MediaItem) {
+        if item.media_type == MediaType::Audio || item.offline {
+            return;
+        }
+        if let Some(requests) = &self.requests {
+            // Only fails once the worker has stopped
+            let _ = requests.send(item.clone());
+        }
+    }
+
+    /// Finished jobs since the last call, without blocking
+    pub fn poll(&self) -> Vec<ThumbnailEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+impl Drop for ThumbnailService {
+    fn drop(&mut self) {
+        // Closing the request channel ends the worker after its current job
+        while self.pending.try_recv().is_ok() {}
+        self.requests.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_keeps_thumbnails_in_use() {
+        let root = std::env::temp_dir().join(format!("vxutil-thumbnails-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let cache = ThumbnailCache::new(root.join("cache"));
+        let mut decode = |_: &MediaItem, _: Timecode| Ok(RgbaImage::new(4, 4));
+
+        // The same file in the library twice
+        let path = root.join("still.png");
+        fs::write(&path, b"first").unwrap();
+        let mut library = MediaLibrary::new();
+        let item = library.add_item(MediaItem::new(path.clone(), MediaType::Image));
+        let duplicate = library.add_item(MediaItem::new(path.clone(), MediaType::Image));
+        let (old, thumbnails) = cache
+            .generate(library.get_item(&item).unwrap(), &mut decode)
+            .unwrap();
+        assert!(thumbnails.poster.is_file());
+        assert!(!thumbnails.poster.with_extension("png.part").exists());
+
+        // A changed file gets new thumbnails; the old ones wait for a prune
+        fs::write(&path, b"second").unwrap();
+        let (new, _) = cache
+            .generate(library.get_item(&item).unwrap(), &mut decode)
+            .unwrap();
+        assert_ne!(new, old);
+        assert!(cache.get(&old, None).is_some());
+
+        // Partial files of interrupted writes go too
+        let partial = cache.entry_dir(&new).join("strip-000.png.part");
+        fs::write(&partial, b"").unwrap();
+        assert_eq!(cache.prune(&library).unwrap(), 1);
+        assert!(cache.get(&old, None).is_none());
+        assert!(!partial.exists());
+
+        library.remove_item(&item);
+        assert_eq!(cache.prune(&library).unwrap(), 0);
+        assert!(cache.get(&new, None).is_some());
+        library.remove_item(&duplicate);
+        assert_eq!(cache.prune(&library).unwrap(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,564 @@
+//! Audio waveform peaks with an on-disk cache
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use vxutil_core::media::{MediaItem, MediaLibrary, MediaType};
+use vxutil_core::{TimeRange, Timecode};
+
+use crate::content_cache::{self, ContentCache, ContentKey};
+use crate::{EngineError, Result};
+
+/// Samples per peak of the finest level
+pub const BASE_SAMPLES_PER_PEAK: u32 = 256;
+
+/// Each level covers this many peaks of the level below
+const LEVEL_FACTOR: u32 = 4;
+
+/// Levels stop once a channel fits in this many peaks
+const MIN_LEVEL_PEAKS: usize = 64;
+
+const MAGIC: &[u8; 4] = b"VXPK";
+const VERSION: u32 = 1;
+
+/// Smallest and largest sample of a run of samples, full scale at `i16::MAX`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Peak {
+    pub min: i16,
+    pub max: i16,
+}
+
+impl Peak {
+    /// Peak of a run with no samples yet
+    const EMPTY: Self = Self {
+        min: i16::MAX,
+        max: i16::MIN,
+    };
+
+    fn add(&mut self, sample: f32) {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn merge(&mut self, other: Peak) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+}
+
+/// Peaks of every channel at one resolution
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakLevel {
+    pub samples_per_peak: u32,
+
+    /// One list of peaks per channel, all the same length
+    pub channels: Vec<Vec<Peak>>,
+}
+
+impl PeakLevel {
+    fn coarser(&self) -> Self {
+        let channels = self
+            .channels
+            .iter()
+            .map(|peaks| {
+                peaks
+                    .chunks(LEVEL_FACTOR as usize)
+                    .map(|chunk| {
+                        let mut peak = Peak::EMPTY;
+                        chunk.iter().for_each(|p| peak.merge(*p));
+                        peak
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            samples_per_peak: self.samples_per_peak * LEVEL_FACTOR,
+            channels,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.channels.first().map_or(0, Vec::len)
+    }
+}
+
+/// Peaks of a source range, returned by [`Waveform::query`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformPeaks {
+    pub samples_per_peak: u32,
+
+    /// Source time of the first peak; may be a little before the requested range
+    pub start: Timecode,
+
+    /// Seconds covered by each peak
+    pub seconds_per_peak: f64,
+
+    pub channels: Vec<Vec<Peak>>,
+}
+
+/// Min/max peaks of an audio stream at several resolutions. The finest level
+/// holds one peak per [`BASE_SAMPLES_PER_PEAK`] samples, each coarser level one
+/// peak per four of the level below.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    pub sample_rate: u32,
+    pub channels: u16,
+
+    /// Finest level first
+    pub levels: Vec<PeakLevel>,
+}
+
+impl Waveform {
+    /// Peaks covering a source range for a zoom level of `seconds_per_pixel`. Uses
+    /// the coarsest level that still has at least one peak per pixel.
+    pub fn query(&self, range: TimeRange, seconds_per_pixel: f64) -> WaveformPeaks {
+        let samples_per_pixel = seconds_per_pixel * self.sample_rate as f64;
+        let level = self
+            .levels
+            .iter()
+            .rev()
+            .find(|level| level.samples_per_peak as f64 <= samples_per_pixel)
+            .or(self.levels.first());
+        let Some(level) = level else {
+            return WaveformPeaks {
+                samples_per_peak: BASE_SAMPLES_PER_PEAK,
+                start: range.start,
+                seconds_per_peak: BASE_SAMPLES_PER_PEAK as f64 / self.sample_rate.max(1) as f64,
+                channels: vec![Vec::new(); self.channels as usize],
+            };
+        };
+
+        let seconds_per_peak = level.samples_per_peak as f64 / self.sample_rate.max(1) as f64;
+        let first =
+            ((range.start.as_seconds() / seconds_per_peak).floor() as usize).min(level.len());
+        let last = ((range.end().as_seconds() / seconds_per_peak).ceil() as usize)
+            .clamp(first, level.len());
+        WaveformPeaks {
+            samples_per_peak: level.samples_per_peak,
+            start: Timecode::from_seconds(first as f64 * seconds_per_peak),
+            seconds_per_peak,
+            channels: level
+                .channels
+                .iter()
+                .map(|peaks| peaks[first..last].to_vec())
+                .collect(),
+        }
+    }
+
+    /// Save in the binary peak file format: a header followed by each level's
+    /// peaks, channel by channel, as little-endian i16 min/max pairs
+    pub fn write_to(&self, mut writer: impl Write) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        for value in [
+            VERSION,
+            self.sample_rate,
+            self.channels as u32,
+            self.levels.len() as u32,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for level in &self.levels {
+            writer.write_all(&level.samples_per_peak.to_le_bytes())?;
+            writer.write_all(&(level.len() as u32).to_le_bytes())?;
+            for peak in level.channels.iter().flatten() {
+                writer.write_all(&peak.min.to_le_bytes())?;
+                writer.write_all(&peak.max.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load from the format written by [`Waveform::write_to`]
+    pub fn read_from(mut reader: impl Read) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(EngineError::Decode("Not a peak file".to_string()));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(EngineError::Decode(format!(
+                "Unsupported peak file version {version}"
+            )));
+        }
+        let sample_rate = read_u32(&mut reader)?;
+        let channels = u16::try_from(read_u32(&mut reader)?)
+            .ok()
+            .filter(|channels| *channels > 0)
+            .ok_or_else(|| EngineError::Decode("Invalid channel count".to_string()))?;
+        let level_count = read_u32(&mut reader)? as usize;
+
+        let mut levels = Vec::new();
+        for index in 0..level_count {
+            let samples_per_peak = read_u32(&mut reader)?;
+            let count = read_u32(&mut reader)? as usize;
+            // The finest level decides how many coarser levels can follow
+            if index == 0 && level_count > max_levels(count) {
+                return Err(EngineError::Decode("Invalid level count".to_string()));
+            }
+            let mut level = PeakLevel {
+                samples_per_peak,
+                channels: Vec::with_capacity(channels as usize),
+            };
+            for _ in 0..channels {
+                // Grow as data arrives so a corrupt count fails on read, not allocation
+                let mut peaks = Vec::new();
+                let mut bytes = [0u8; 4];
+                for _ in 0..count {
+                    reader.read_exact(&mut bytes)?;
+                    peaks.push(Peak {
+                        min: i16::from_le_bytes([bytes[0], bytes[1]]),
+                        max: i16::from_le_bytes([bytes[2], bytes[3]]),
+                    });
+                }
+                level.channels.push(peaks);
+            }
+            levels.push(level);
+        }
+        Ok(Self {
+            sample_rate,
+            channels,
+            levels,
+        })
+    }
+}
+
+/// Number of levels [`WaveformBuilder::finish`] makes from `finest` peaks per channel
+fn max_levels(finest: usize) -> usize {
+    let mut levels = 1;
+    let mut peaks = finest;
+    while peaks > MIN_LEVEL_PEAKS {
+        peaks = peaks.div_ceil(LEVEL_FACTOR as usize);
+        levels += 1;
+    }
+    levels
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Builds a [`Waveform`] from decoded samples
+pub struct WaveformBuilder {
+    sample_rate: u32,
+    channels: u16,
+    base: Vec<Vec<Peak>>,
+    current: Vec<Peak>,
+
+    /// Frames (one sample per channel) in the current peaks
+    filled: u32,
+}
+
+impl WaveformBuilder {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            base: vec![Vec::new(); channels as usize],
+            current: vec![Peak::EMPTY; channels as usize],
+            filled: 0,
+        }
+    }
+
+    /// Add interleaved samples in the range -1.0 to 1.0
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.channels == 0 {
+            return;
+        }
+        for frame in samples.chunks_exact(self.channels as usize) {
+            for (peak, sample) in self.current.iter_mut().zip(frame) {
+                peak.add(*sample);
+            }
+            self.filled += 1;
+            if self.filled == BASE_SAMPLES_PER_PEAK {
+                self.flush();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Waveform {
+        if self.filled > 0 {
+            self.flush();
+        }
+        let mut levels = vec![PeakLevel {
+            samples_per_peak: BASE_SAMPLES_PER_PEAK,
+            channels: self.base,
+        }];
+        while let Some(level) = levels.last()
+            && level.len() > MIN_LEVEL_PEAKS
+        {
+            let coarser = level.coarser();
+            levels.push(coarser);
+        }
+        Waveform {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            levels,
+        }
+    }
+
+    fn flush(&mut self) {
+        for (peaks, current) in self.base.iter_mut().zip(&mut self.current) {
+            peaks.push(std::mem::replace(current, Peak::EMPTY));
+        }
+        self.filled = 0;
+    }
+}
+
+/// Whether a media item may carry sound. Whether a video file has an audio
+/// stream is only known once it is opened, so all video files count;
+/// [`crate::ffmpeg::build_waveform`] fails cleanly on files without audio.
+pub fn may_have_audio(item: &MediaItem) -> bool {
+    match item.media_type {
+        MediaType::Audio => true,
+        MediaType::Video => item.image_sequence.is_none(),
+        MediaType::Image => false,
+    }
+}
+
+/// Peak files stored below a cache directory, one per [`ContentKey`]
+#[derive(Debug, Clone)]
+pub struct WaveformCache {
+    entries: ContentCache,
+}
+
+impl WaveformCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            entries: ContentCache::new(dir, Some("peaks")),
+        }
+    }
+
+    /// Peak file of some content
+    pub fn entry_path(&self, key: &ContentKey) -> PathBuf {
+        self.entries.entry_path(key)
+    }
+
+    /// Cached waveform of some content, if its peak file exists
+    pub fn get(&self, key: &ContentKey) -> Result<Option<Waveform>> {
+        let path = self.entry_path(key);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Waveform::read_from(BufReader::new(File::open(path)?)).map(Some)
+    }
+
+    pub fn store(&self, key: &ContentKey, waveform: &Waveform) -> Result<()> {
+        fs::create_dir_all(self.entries.dir())?;
+        content_cache::write_atomic(&self.entry_path(key), |partial| {
+            let mut writer = BufWriter::new(File::create(partial)?);
+            waveform.write_to(&mut writer)?;
+            writer.flush()?;
+            Ok(())
+        })
+    }
+
+    /// Delete the peak file of some content
+    pub fn remove(&self, key: &ContentKey) -> Result<()> {
+        self.entries.remove(key)
+    }
+
+    /// Delete the peak files of content no item in the library has any more, as
+    /// described at [`ContentCache::prune`]. Returns the number of files deleted.
+    pub fn prune(&self, library: &MediaLibrary) -> Result<usize> {
+        self.entries.prune(library, may_have_audio)
+    }
+
+    /// Waveform of a media item, building it with `build` (e.g.
+    /// [`crate::ffmpeg::build_waveform`]) if it is not cached. The key of the
+    /// item's current content is computed and returned with its peaks. Peak files
+    /// of older content are left for [`WaveformCache::prune`], as duplicates of the
+    /// item may still use them.
+    pub fn get_or_build(
+        &self,
+        item: &MediaItem,
+        build: &mut dyn FnMut(&Path) -> Result<Waveform>,
+    ) -> Result<(ContentKey, Waveform)> {
+        if !may_have_audio(item) {
+            return Err(EngineError::Decode(format!("{} has no audio", item.name)));
+        }
+        let key = ContentKey::compute(item)?;
+
+        // An unreadable peak file is rebuilt rather than reported
+        if let Ok(Some(waveform)) = self.get(&key) {
+            return Ok((key, waveform));
+        }
+        let waveform = build(&item.path)?;
+        self.store(&key, &waveform)?;
+        Ok((key, waveform))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Stereo waveform of `frames` frames: a slow ramp on the left channel, silence
+    /// on the right except for one full-scale spike
+    fn waveform(frames: usize, spike: usize) -> Waveform {
+        let mut builder = WaveformBuilder::new(48_000, 2);
+        let mut samples = Vec::with_capacity(frames * 2);
+        for frame in 0..frames {
+            samples.push(frame as f32 / frames as f32);
+            samples.push(if frame == spike { -1.0 } else { 0.0 });
+        }
+        // Odd chunk sizes so peaks straddle push calls
+        for chunk in samples.chunks(2 * 1000) {
+            builder.push(chunk);
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn test_levels_reduce_by_four() {
+        let waveform = waveform(256 * 280, 5000);
+        let sizes: Vec<_> = waveform
+            .levels
+            .iter()
+            .map(|level| (level.samples_per_peak, level.len()))
+            .collect();
+        assert_eq!(sizes, vec![(256, 280), (1024, 70), (4096, 18)]);
+
+        // The spike survives in the peak covering it at every level
+        for level in &waveform.levels {
+            let index = 5000 / level.samples_per_peak as usize;
+            assert_eq!(level.channels[1][index].min, -i16::MAX);
+            assert_eq!(level.channels[1][index + 1].min, 0);
+        }
+        let last = waveform.levels[2].channels[0].last().unwrap();
+        assert!(last.max > i16::MAX - 10 && last.min < last.max);
+    }
+
+    #[test]
+    fn test_partial_last_peak() {
+        let waveform = waveform(300, 299);
+        assert_eq!(waveform.levels.len(), 1);
+        assert_eq!(waveform.levels[0].channels[1].len(), 2);
+        assert_eq!(waveform.levels[0].channels[1][1].min, -i16::MAX);
+    }
+
+    #[test]
+    fn test_query_picks_level_and_clamps() {
+        let waveform = waveform(256 * 280, 5000);
+        let range = |start: f64, seconds: f64| {
+            TimeRange::new(
+                Timecode::from_seconds(start),
+                Duration::from_secs_f64(seconds),
+            )
+        };
+
+        // 2000 samples per pixel: the 1024 level is the coarsest with a peak per pixel
+        let peaks = waveform.query(range(0.0, 1.0), 2000.0 / 48_000.0);
+        assert_eq!(peaks.samples_per_peak, 1024);
+        assert_eq!(peaks.channels.len(), 2);
+        assert_eq!(peaks.channels[0].len(), 47);
+
+        // Zoomed in past the finest level
+        let peaks = waveform.query(range(0.0, 0.1), 1.0 / 48_000.0);
+        assert_eq!(peaks.samples_per_peak, 256);
+
+        // Zoomed far out
+        let peaks = waveform.query(range(0.0, 2.0), 1.0);
+        assert_eq!(peaks.samples_per_peak, 4096);
+        assert_eq!(peaks.channels[0].len(), 18);
+
+        // Starts on the peak containing the range start
+        let peaks = waveform.query(range(0.5, 0.1), 2000.0 / 48_000.0);
+        assert_eq!(
+            peaks.start,
+            Timecode::from_seconds(23.0 * 1024.0 / 48_000.0)
+        );
+
+        // Clamped to the end of the audio
+        let peaks = waveform.query(range(1.0, 10.0), 2000.0 / 48_000.0);
+        assert_eq!(peaks.channels[0].len(), 70 - 46);
+        let peaks = waveform.query(range(100.0, 1.0), 2000.0 / 48_000.0);
+        assert!(peaks.channels.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let waveform = waveform(256 * 100, 10);
+        let mut bytes = Vec::new();
+        waveform.write_to(&mut bytes).unwrap();
+        assert_eq!(Waveform::read_from(bytes.as_slice()).unwrap(), waveform);
+    }
+
+    #[test]
+    fn test_corrupt_files_are_rejected() {
+        let mut bytes = Vec::new();
+        waveform(1000, 0).write_to(&mut bytes).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(Waveform::read_from(bad_magic.as_slice()).is_err());
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 99;
+        assert!(Waveform::read_from(bad_version.as_slice()).is_err());
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(Waveform::read_from(truncated).is_err());
+        assert!(Waveform::read_from(&bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_header_is_rejected() {
+        let mut bytes = Vec::new();
+        waveform(256 * 100, 0).write_to(&mut bytes).unwrap();
+        let with = |offset: usize, value: u32| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            Waveform::read_from(bytes.as_slice())
+        };
+
+        assert!(matches!(with(12, 0), Err(EngineError::Decode(_))));
+        // 100 finest peaks allow one coarser level
+        assert!(with(16, 2).is_ok());
+        assert!(matches!(with(16, 3), Err(EngineError::Decode(_))));
+        assert!(matches!(with(16, u32::MAX), Err(EngineError::Decode(_))));
+    }
+
+    #[test]
+    fn test_cache_rebuilds_changed_files_and_prunes() {
+        let root = std::env::temp_dir().join(format!("vxutil-waveform-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let cache = WaveformCache::new(root.join("cache"));
+        let mut builds = 0;
+        let mut build = |_: &Path| {
+            builds += 1;
+            Ok(waveform(1000, 0))
+        };
+
+        let path = root.join("voice.wav");
+        fs::write(&path, b"first").unwrap();
+        let mut library = MediaLibrary::new();
+        let id = library.add_item(MediaItem::new(path.clone(), MediaType::Audio));
+        let item = library.get_item(&id).unwrap().clone();
+        let (old, built) = cache.get_or_build(&item, &mut build).unwrap();
+        let (_, cached) = cache.get_or_build(&item, &mut build).unwrap();
+        assert_eq!(cached, built);
+
+        fs::write(&path, b"second").unwrap();
+        let (new, _) = cache.get_or_build(&item, &mut build).unwrap();
+        assert_ne!(new, old);
+        assert_eq!(builds, 2);
+
+        // Left by an interrupted write
+        let partial = root.join("cache").join("stale.peaks.part");
+        fs::write(&partial, b"").unwrap();
+        assert_eq!(cache.prune(&library).unwrap(), 1);
+        assert!(!cache.entry_path(&old).exists());
+        assert!(cache.entry_path(&new).is_file());
+        assert!(!partial.exists());
+
+        library.remove_item(&id);
+        assert_eq!(cache.prune(&library).unwrap(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}